@@ -1,4 +1,5 @@
 use crate::types;
+use crate::types::ConstantTable;
 use crate::types::Rpn;
 use crate::types::Statement;
 use crate::types::StatementType;
@@ -96,9 +97,38 @@ struct Environment {
 	name: String,
 	definitions: HashMap<String, types::Definition>,
 	pool: u16,
+	// Constants declared within this environment or imported by `use`.
+	symbols: SymbolTable,
+	// The type of literals and unknown symbols, set by `default int = <type>;`.
+	default_integer: Primative,
+	// The type of addresses, set by `pointer = <type>;`.
+	pointer: Primative,
+	// Variables shared by every script using this environment, in the order they are placed at the start of the pool.
+	variables: Vec<(String, Type)>,
 }
 
 impl Environment {
+	fn define_variable(&mut self, name: &str, t: Type) -> Result<(), String> {
+		if self.variables.iter().any(|(i, _)| i == name) {
+			return Err(format!("Shared variable {name} is declared more than once"));
+		}
+
+		let size = self.shared_size() as usize + t.size() as usize;
+		if size > 256 {
+			return Err(format!(
+				"{name} does not fit; shared variables are limited to 256 bytes"
+			));
+		}
+
+		self.variables.push((String::from(name), t));
+		Ok(())
+	}
+
+	/// The number of bytes at the start of the pool reserved for shared variables.
+	fn shared_size(&self) -> u16 {
+		self.variables.iter().map(|(_, t)| t.size() as u16).sum()
+	}
+
 	fn expand(&self, name: &str) -> Result<String, String> {
 		match self.lookup(name)? {
 			types::Definition::Def(..) => Ok(format!("{}@{}", self.name, name)),
@@ -117,19 +147,38 @@ impl Environment {
 
 type EnvironmentTable = HashMap<String, Environment>;
 
+/// What a caller needs to know about a compiled script in order to `call` it.
+#[derive(Debug, Clone)]
+struct Script {
+	environment: String,
+	// The bytecode of each of the environment's definitions, which the caller's environment must agree with.
+	bytecodes: HashMap<String, u8>,
+	// The environment's shared variables, which must begin the caller's shared variables.
+	variables: Vec<(String, Type)>,
+	// The offset and type of each parameter.
+	params: Vec<(u8, Type)>,
+	return_type: Option<Primative>,
+	return_offset: u8,
+	peak_usage: u8,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum Type {
 	Primative(Primative),
-	Pointer(Box<Type>),
-	Struct(Vec<(String, Type)>),
+	// A pointer also records the primative its address is stored as.
+	Pointer(Box<Type>, Primative),
+	Struct(String, Vec<(String, Type)>),
+	Array(Box<Type>, u8),
+	// An enum is stored as its primative, but is a distinct type from it and from other enums.
+	Enum(String, Primative),
 }
 
 impl Type {
 	fn size(&self) -> u8 {
 		match self {
-			Type::Primative(t) => t.size,
-			Type::Pointer(_) => Primative::pointer().size,
-			Type::Struct(t) => {
+			Type::Primative(t) | Type::Enum(_, t) => t.size,
+			Type::Pointer(_, t) => t.size,
+			Type::Struct(_, t) => {
 				let mut this_size = 0;
 
 				for (_, i) in t {
@@ -138,6 +187,102 @@ impl Type {
 
 				this_size
 			}
+			Type::Array(t, length) => t.size() * length,
+		}
+	}
+
+	/// The primative an integer type is stored as, including the underlying type of an enum.
+	fn as_primative(&self) -> Option<Primative> {
+		match self {
+			Type::Primative(t) | Type::Enum(_, t) => Some(*t),
+			Type::Pointer(..) | Type::Struct(..) | Type::Array(..) => None,
+		}
+	}
+
+	fn is_aggregate(&self) -> bool {
+		matches!(self, Type::Struct(..) | Type::Array(..))
+	}
+
+	/// Returns the offset and type of a struct member.
+	fn member(&self, name: &str) -> Result<(u8, &Type), String> {
+		match self {
+			Type::Struct(struct_name, members) => {
+				let mut offset = 0;
+
+				for (member_name, member) in members {
+					if member_name == name {
+						return Ok((offset, member));
+					}
+					offset += member.size();
+				}
+
+				Err(format!("{name} is not a member of {struct_name}"))
+			}
+			_ => Err(format!("{self} has no members")),
+		}
+	}
+
+	/// Walks a dotted list of struct members, such as `position.x`, returning the offset and type of the last one.
+	fn member_path(&self, parent: &str, path: &str) -> Result<(u8, &Type), String> {
+		let mut offset = 0;
+		let mut t = self;
+		let mut parent = String::from(parent);
+
+		for component in path.split('.') {
+			if !matches!(t, Type::Struct(..)) {
+				return Err(format!("{parent} is a {t} and has no members"));
+			}
+			let (member_offset, member) = t.member(component)?;
+			offset += member_offset;
+			t = member;
+			parent = format!("{parent}.{component}");
+		}
+
+		Ok((offset, t))
+	}
+
+	/// Returns the primative or pointer found `offset` bytes into this type.
+	fn member_at(&self, offset: usize) -> Option<&Type> {
+		match self {
+			Type::Primative(..) | Type::Pointer(..) | Type::Enum(..) => {
+				if offset == 0 {
+					Some(self)
+				} else {
+					None
+				}
+			}
+			Type::Struct(_, members) => {
+				let mut member_offset = 0;
+
+				for (_, i) in members {
+					let member_size = i.size() as usize;
+					if offset < member_offset + member_size {
+						return i.member_at(offset - member_offset);
+					}
+					member_offset += member_size;
+				}
+
+				None
+			}
+			Type::Array(t, length) => {
+				let element_size = t.size() as usize;
+				if offset < element_size * *length as usize {
+					t.member_at(offset % element_size)
+				} else {
+					None
+				}
+			}
+		}
+	}
+}
+
+impl fmt::Display for Type {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Type::Primative(t) => write!(f, "{t}"),
+			Type::Pointer(t, _) => write!(f, "{t} ptr"),
+			Type::Struct(name, _) | Type::Enum(name, _) => write!(f, "{name}"),
+			Type::Array(t, length) => write!(f, "{t}[{length}]"),
 		}
 	}
 }
@@ -146,6 +291,8 @@ impl Type {
 struct Primative {
 	signed: bool,
 	size: u8,
+	/// A `bool` is stored like a u8, but is always either 0 or 1.
+	boolean: bool,
 }
 
 impl Primative {
@@ -153,28 +300,113 @@ impl Primative {
 		Primative {
 			signed: l.signed || r.signed,
 			size: if l.size >= r.size { l.size } else { r.size },
+			boolean: l.boolean && r.boolean,
 		}
 	}
 
-	// TODO: make the default_integer type configurable
+	/// The default integer type of an environment which does not declare one.
 	fn default_integer() -> Primative {
 		Primative {
 			signed: false,
 			size: 1,
+			boolean: false,
+		}
+	}
+
+	/// A single unsigned byte, which 8-bit bytecode works on.
+	fn byte() -> Primative {
+		Primative {
+			signed: false,
+			size: 1,
+			boolean: false,
+		}
+	}
+
+	fn boolean() -> Primative {
+		Primative {
+			signed: false,
+			size: 1,
+			boolean: true,
 		}
 	}
 
-	// TODO: make the default pointer type configurable
+	/// The pointer type of an environment which does not declare one.
 	fn pointer() -> Primative {
 		Primative {
 			signed: false,
 			size: 2,
+			boolean: false,
+		}
+	}
+
+	/// This type as an integer, which is how a `bool` is treated by bytecode.
+	fn integer(&self) -> Primative {
+		Primative {
+			boolean: false,
+			..*self
+		}
+	}
+
+	/// The smallest and largest values this type can hold.
+	fn range(&self) -> (i64, i64) {
+		let bits = self.size as u32 * 8;
+		if self.boolean {
+			(0, 1)
+		} else if self.signed {
+			(-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+		} else {
+			(0, (1 << bits) - 1)
+		}
+	}
+
+	/// The name of the bytecode which performs `op` on this type.
+	/// Only operations which treat negative values differently have signed bytecode; the rest share the unsigned one.
+	fn opcode(&self, op: &str) -> String {
+		if self.boolean
+			|| self.signed && !matches!(op, "lt" | "gt" | "lte" | "gte" | "div" | "mod" | "shr")
+		{
+			format!("{op}_u{}", self.size * 8)
+		} else {
+			format!("{op}_{self}")
+		}
+	}
+
+	fn contains(&self, value: i64) -> bool {
+		let (min, max) = self.range();
+		(min..=max).contains(&value)
+	}
+
+	fn check(&self, value: i64) -> Result<(), String> {
+		if self.contains(value) {
+			Ok(())
+		} else {
+			let (min, max) = self.range();
+			Err(format!(
+				"{value} does not fit in a {self}, which ranges from {min} to {max}"
+			))
 		}
 	}
+
+	/// The type of an integer literal: `preferred` if the value fits, or otherwise the smallest type that does.
+	fn literal(value: i64, preferred: Primative) -> Result<Primative, String> {
+		std::iter::once(preferred)
+			.chain((1..=4).map(|size| Primative {
+				signed: value < 0,
+				size,
+				boolean: false,
+			}))
+			.find(|t| t.contains(value))
+			.ok_or(format!(
+				"{value} is too large, integers may be up to 32 bits"
+			))
+	}
 }
 
 impl fmt::Display for Primative {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.boolean {
+			return write!(f, "bool");
+		}
 		write!(
 			f,
 			"{}{}",
@@ -195,16 +427,228 @@ impl TypeTable {
 			None => Err(format!("Type {name} not found")),
 		}
 	}
+}
+
+/// A name which refers to something other than a local variable.
+#[derive(Debug, Clone)]
+enum Symbol {
+	// An enum variant is a constant of the enum's type.
+	Constant(Type, i64),
+	// An assembly label pointing to a variable of this type.
+	Extern(Type),
+	// An assembly constant, whose value is only known to the assembler.
+	ExternConstant(Primative),
+	// A script which has already been compiled, and may be called by later scripts.
+	Script(Script),
+}
+
+#[derive(Debug, Clone, Default)]
+struct SymbolTable {
+	table: HashMap<String, Symbol>,
+	// Maps strings of one or more characters to the bytes they are encoded as.
+	charmap: HashMap<String, Vec<u8>>,
+}
+
+impl SymbolTable {
+	fn lookup_constant(&self, name: &str) -> Option<(Primative, i64)> {
+		match self.table.get(name) {
+			Some(Symbol::Constant(t, value)) => Some((t.as_primative()?, *value)),
+			_ => None,
+		}
+	}
+
+	fn lookup_constant_type(&self, name: &str) -> Option<&Type> {
+		match self.table.get(name) {
+			Some(Symbol::Constant(t, _)) => Some(t),
+			_ => None,
+		}
+	}
+
+	fn lookup_script(&self, name: &str) -> Option<&Script> {
+		match self.table.get(name) {
+			Some(Symbol::Script(script)) => Some(script),
+			_ => None,
+		}
+	}
 
-	fn lookup_primative(&self, name: &str) -> Result<Primative, String> {
+	fn lookup_extern_constant(&self, name: &str) -> Option<Primative> {
 		match self.table.get(name) {
-			Some(t) => match t {
-				Type::Primative(result) => Ok(*result),
-				Type::Pointer(_) => panic!("A type should never be declared as a pointer"),
-				Type::Struct(..) => Err(format!("{name} must be a primative type")),
+			Some(Symbol::ExternConstant(t)) => Some(*t),
+			_ => None,
+		}
+	}
+
+	/// Finds an extern variable or one of its members, such as `wPlayerSprite.x`.
+	/// Returns the member's address as an assembly expression, and its type.
+	fn lookup_extern(&self, name: &str) -> Result<Option<(String, Type)>, String> {
+		let (symbol_name, members) = match name.split_once('.') {
+			Some((symbol_name, members)) => (symbol_name, Some(members)),
+			None => (name, None),
+		};
+
+		let Some(Symbol::Extern(t)) = self.table.get(symbol_name) else {
+			return Ok(None);
+		};
+
+		Ok(Some(match members {
+			Some(members) => match t.member_path(symbol_name, members)? {
+				(0, member_type) => (String::from(symbol_name), member_type.clone()),
+				(offset, member_type) => {
+					(format!("({symbol_name} + {offset})"), member_type.clone())
+				}
 			},
-			None => Err(format!("Type {name} not found")),
+			None => (String::from(symbol_name), t.clone()),
+		}))
+	}
+
+	fn define_symbol(&mut self, name: &str, symbol: Symbol) -> Result<(), String> {
+		if self.table.contains_key(name) {
+			return Err(format!("{name} is already defined"));
+		}
+
+		self.table.insert(String::from(name), symbol);
+		Ok(())
+	}
+
+	fn define_constant(&mut self, name: &str, t: Type, value: i64) -> Result<(), String> {
+		if self.table.contains_key(name) {
+			return Err(format!("{name} is already defined"));
+		}
+
+		let Some(primative) = t.as_primative() else {
+			return Err(format!("Constants must be integers, not {t}"));
+		};
+		if !primative.contains(value) {
+			let (min, max) = primative.range();
+			return Err(format!(
+				"Constant {name} ({value}) does not fit in a {t}, which ranges from {min} to {max}"
+			));
+		}
+
+		self.table
+			.insert(String::from(name), Symbol::Constant(t, value));
+		Ok(())
+	}
+
+	/// Returns a copy of this table with all of `other`'s symbols added.
+	fn merge(&self, other: &SymbolTable) -> SymbolTable {
+		let mut merged = self.clone();
+		for (name, symbol) in &other.table {
+			merged.table.insert(name.clone(), symbol.clone());
+		}
+		merged
+	}
+
+	fn eval(&self, rpn: &Rpn) -> Result<i64, String> {
+		rpn.eval_const(self)
+	}
+
+	/// Like `eval`, but local variables take priority over constants of the same name.
+	fn eval_local(&self, rpn: &Rpn, vtable: &VariableTable) -> Result<i64, String> {
+		rpn.eval_const(&LocalConstants {
+			symbols: self,
+			vtable,
+		})
+	}
+
+	fn define_charmap(&mut self, text: String, values: &[Rpn]) -> Result<(), String> {
+		if text.is_empty() {
+			return Err(String::from("A charmap entry may not be empty"));
+		}
+		if values.is_empty() {
+			return Err(format!("Charmap entry {text:?} has no value"));
 		}
+
+		let mut bytes = Vec::<u8>::new();
+		for value in values {
+			let value = self.eval(value)?;
+			if !(0..=255).contains(&value) {
+				return Err(format!(
+					"Charmap entry {text:?} has a value of {value}, which does not fit in a byte"
+				));
+			}
+			bytes.push(value as u8);
+		}
+
+		if self.charmap.insert(text.clone(), bytes).is_some() {
+			eprintln!("WARN: charmap entry {text:?} is defined more than once");
+		}
+		Ok(())
+	}
+
+	/// Encodes a string by matching the longest charmap entry at each position.
+	/// Characters which aren't in the charmap are written as-is.
+	fn encode(&self, text: &str) -> Vec<u8> {
+		let mut bytes = Vec::<u8>::new();
+		let mut remaining = text;
+
+		while let Some(c) = remaining.chars().next() {
+			let entry = self
+				.charmap
+				.iter()
+				.filter(|(entry, _)| remaining.starts_with(entry.as_str()))
+				.max_by_key(|(entry, _)| entry.len());
+
+			if let Some((entry, value)) = entry {
+				bytes.extend(value);
+				remaining = &remaining[entry.len()..];
+				continue;
+			}
+
+			if !self.charmap.is_empty() && !c.is_control() {
+				eprintln!("WARN: {c:?} is not in the charmap");
+			}
+			// Escapes like `\xFF` produce characters up to U+00FF, which are written as a single byte.
+			if (c as u32) <= 0xFF {
+				bytes.push(c as u8);
+			} else {
+				bytes.extend(c.to_string().bytes());
+			}
+			remaining = &remaining[c.len_utf8()..];
+		}
+
+		bytes
+	}
+}
+
+impl ConstantTable for SymbolTable {
+	fn constant(&self, name: &str) -> Option<i64> {
+		self.lookup_constant(name).map(|(_, value)| value)
+	}
+
+	fn character(&self, text: &str) -> Result<i64, String> {
+		let mut chars = text.chars();
+		if let (true, Some(c), None) = (self.charmap.is_empty(), chars.next(), chars.next()) {
+			return Ok(c as i64);
+		}
+
+		match self.encode(text)[..] {
+			[byte] => Ok(byte as i64),
+			ref bytes => Err(format!(
+				"'{text}' is encoded as {} bytes, but a character must be exactly one byte",
+				bytes.len()
+			)),
+		}
+	}
+}
+
+/// The constants visible within a function, where local variables take priority.
+struct LocalConstants<'a> {
+	symbols: &'a SymbolTable,
+	vtable: &'a VariableTable,
+}
+
+impl ConstantTable for LocalConstants<'_> {
+	fn constant(&self, name: &str) -> Option<i64> {
+		if self.vtable.lookup(name).is_ok() {
+			None
+		} else {
+			self.symbols.constant(name)
+		}
+	}
+
+	fn character(&self, text: &str) -> Result<i64, String> {
+		self.symbols.character(text)
 	}
 }
 
@@ -220,14 +664,24 @@ struct VariableTable {
 	peak_usage: u8,
 	// Used to free variables by scope.
 	scope_level: u32,
+	// The type of the slot at the start of the pool which `return` writes to, if any.
+	return_type: Option<Primative>,
+	// The number of bytes taken by the environment's shared variables, which the return slot follows.
+	shared_size: u8,
+	// The variable holding `Rpn::Current` while a compound assignment's value is compiled.
+	current: Option<u8>,
 	variables: [Option<Variable>; 256],
 }
 
 impl VariableTable {
-	fn new() -> VariableTable {
-		VariableTable {
+	/// Creates a table with the environment's shared variables already in place.
+	fn new(env: &Environment) -> VariableTable {
+		let mut vtable = VariableTable {
 			scope_level: 0,
 			peak_usage: 0,
+			return_type: None,
+			shared_size: 0,
+			current: None,
 			variables: [
 				None, None, None, None, None, None, None, None, None, None, None, None, None, None,
 				None, None, None, None, None, None, None, None, None, None, None, None, None, None,
@@ -249,7 +703,18 @@ impl VariableTable {
 				None, None, None, None, None, None, None, None, None, None, None, None, None, None,
 				None, None, None, None,
 			],
+		};
+
+		// Shared variables are allocated first, so that they always begin at offset 0.
+		for (name, t) in &env.variables {
+			let id = vtable
+				.alloc(t.clone())
+				.expect("Shared variables are limited to 256 bytes");
+			*vtable.name_of(id) = Some(name.clone());
 		}
+		vtable.shared_size = vtable.peak_usage;
+
+		vtable
 	}
 
 	fn alloc(&mut self, t: Type) -> Result<u8, String> {
@@ -259,6 +724,16 @@ impl VariableTable {
 			match &self.variables[i] {
 				Some(var) => i += var.t.size() as usize,
 				None => {
+					// Make sure the entire variable fits before claiming this space.
+					let end = i + t.size() as usize;
+					if end > 256 {
+						break;
+					}
+					if let Some(occupied) = (i..end).find(|j| self.variables[*j].is_some()) {
+						i = occupied;
+						continue;
+					}
+
 					let this_peak = i as u8 + t.size();
 					if self.peak_usage < this_peak {
 						self.peak_usage = this_peak;
@@ -297,80 +772,62 @@ impl VariableTable {
 	}
 
 	fn lookup(&self, name: &str) -> Result<u8, String> {
-		let mut i = 0;
+		Ok(self.lookup_with_type(name)?.0)
+	}
 
-		let components: Vec<&str> = name.split('.').collect();
+	/// Finds a variable or struct member, returning its index and type.
+	fn lookup_with_type(&self, name: &str) -> Result<(u8, &Type), String> {
+		let (variable_name, members) = match name.split_once('.') {
+			Some((variable_name, members)) => (variable_name, Some(members)),
+			None => (name, None),
+		};
+		let mut i = 0;
 
-		if components.len() == 1 {
-			while i < 256 {
-				if let Some(variable) = &self.variables[i] {
-					if let Some(variable_name) = &variable.name {
-						if variable_name == name {
-							return Ok(i as u8);
-						}
-					}
-					i += variable.t.size() as usize;
-				} else {
-					i += 1;
-				}
-			}
-		} else {
-			while i < 256 {
-				if let Some(variable) = &self.variables[i] {
-					if let Some(variable_name) = &variable.name {
-						if let Type::Struct(struct_type) = &variable.t {
-							if variable_name == components[0] {
-								// Now that we've found a struct, we'll traverse it to find the member.
-								let mut comp_i = 1;
-								'next_component: while comp_i < components.len() {
-									let mut offset = 0;
-
-									for (member_name, member) in struct_type {
-										if member_name == components[comp_i] {
-											match member {
-												Type::Primative(primative) => {
-													if comp_i + 1 != components.len() {
-														return Err(format!("{member_name} is a {primative} and has no members"));
-													}
-													return Ok((i + offset) as u8);
-												}
-												Type::Pointer(..) => {
-													if comp_i + 1 != components.len() {
-														return Err(format!("{member_name} is a pointer and has no members"));
-													}
-													return Ok((i + offset) as u8);
-												}
-												Type::Struct(..) => {
-													if comp_i + 1 == components.len() {
-														return Ok((i + offset) as u8);
-													} else {
-														comp_i += 1;
-														continue 'next_component;
-													}
-												}
-											}
-										}
-
-										offset += member.size() as usize;
-									}
-									break;
-								}
-								return Err(format!("{name} is not a member of {variable_name}"));
-							}
-						} else {
-							return Err(format!("{} is not a struct", components[0]));
+		while i < 256 {
+			if let Some(variable) = &self.variables[i] {
+				if variable.name.as_deref() == Some(variable_name) {
+					return match members {
+						// Now that we've found the variable, traverse it to find the member.
+						Some(members) => {
+							let (offset, t) = variable.t.member_path(variable_name, members)?;
+							Ok((i as u8 + offset, t))
 						}
-					}
-					i += variable.t.size() as usize;
-				} else {
-					i += 1;
+						None => Ok((i as u8, &variable.t)),
+					};
 				}
+				i += variable.t.size() as usize;
+			} else {
+				i += 1;
 			}
 		}
 
 		Err(format!("Variable {name} does not exist"))
 	}
 
+	fn lookup_array(&self, name: &str) -> Result<(u8, Type, u8), String> {
+		let base = self.lookup(name)?;
+
+		match &self.variables[base as usize] {
+			Some(Variable {
+				t: Type::Array(t, length),
+				..
+			}) => Ok((base, *t.clone(), *length)),
+			_ => Err(format!("{name} is not an array")),
+		}
+	}
+
+	/// Returns true if `i` is an unnamed temporary, rather than a variable or part of one.
+	fn is_temporary(&self, i: u8) -> bool {
+		matches!(&self.variables[i as usize], Some(var) if var.name.is_none())
+	}
+
+	fn type_of_mut(&mut self, i: u8) -> &mut Type {
+		match &mut self.variables[i as usize] {
+			Some(var) => &mut var.t,
+			None => panic!("Variable index {i} does not exist"),
+		}
+	}
+
 	fn name_of(&mut self, i: u8) -> &mut Option<String> {
 		match &mut self.variables[i as usize] {
 			Some(var) => &mut var.name,
@@ -379,74 +836,21 @@ impl VariableTable {
 	}
 
 	fn is_pointer(&self, id: u8) -> bool {
-		let id = id as usize;
-
-		match &self.variables[id] {
-			Some(var) => matches!(var.t, Type::Pointer(..)),
-			None => panic!("Variable index {id} does not exist"),
-		}
+		matches!(self.type_at(id), Type::Pointer(..))
 	}
 
-	fn type_of(&mut self, id: u8) -> Primative {
+	/// Finds the primative or pointer at a given index, looking inside of structs and arrays.
+	fn type_at(&self, id: u8) -> &Type {
 		let id = id as usize;
-
-		match &self.variables[id] {
-			Some(var) => match var.t {
-				Type::Primative(result) => return result,
-				Type::Pointer(..) => return Primative::pointer(),
-				Type::Struct(..) => {}
-			},
-			None => {}
-		}
-
-		// If the variable does not exist, there's a chance it's a struct member.
-		// Walk backwards until a structure is found, then check if it has a member at the provided index.
 		let mut index = id;
 
+		// Walk backwards until the variable containing this index is found.
 		loop {
-			match &self.variables[index] {
-				Some(var) => {
-					fn seek_struct(
-						members: &Vec<(String, Type)>,
-						id: usize,
-						mut member_offset: usize,
-					) -> Primative {
-						for (_, i) in members {
-							if id < member_offset {
-								panic!("Variable index {id} does not exist");
-							}
-
-							match i {
-								Type::Primative(primative) => {
-									if id == member_offset {
-										return *primative;
-									}
-								}
-								Type::Pointer(..) => {
-									if id == member_offset {
-										return Primative::pointer();
-									}
-								}
-								Type::Struct(members) => {
-									if id < member_offset + i.size() as usize {
-										return seek_struct(members, id, member_offset);
-									}
-								}
-							}
-
-							member_offset += i.size() as usize;
-						}
-						panic!("Variable index {id} does not exist");
-					}
-
-					match &var.t {
-						Type::Primative(..) | Type::Pointer(..) => break,
-						Type::Struct(members) => {
-							return seek_struct(members, id, index);
-						}
-					}
+			if let Some(var) = &self.variables[index] {
+				match var.t.member_at(id - index) {
+					Some(t) => return t,
+					None => break,
 				}
-				None => {}
 			}
 
 			if let Some(result) = index.checked_sub(1) {
@@ -458,6 +862,32 @@ impl VariableTable {
 		panic!("Variable index {id} does not exist");
 	}
 
+	fn type_of(&self, id: u8) -> Primative {
+		match self.type_at(id) {
+			Type::Primative(result) | Type::Enum(_, result) => *result,
+			Type::Pointer(_, t) => *t,
+			Type::Struct(..) | Type::Array(..) => unreachable!(),
+		}
+	}
+
+	/// The end of the highest variable which is currently allocated.
+	fn usage(&self) -> u16 {
+		let mut end = 0;
+		let mut i = 0;
+
+		while i < 256 {
+			match &self.variables[i] {
+				Some(variable) => {
+					i += variable.t.size() as usize;
+					end = i;
+				}
+				None => i += 1,
+			}
+		}
+
+		end as u16
+	}
+
 	fn push_scope(&mut self) {
 		self.scope_level += 1;
 	}
@@ -480,21 +910,76 @@ impl VariableTable {
 	}
 }
 
+/// The labels of an enclosing loop, which `break` and `continue` jump to.
+struct LoopLabels {
+	name: Option<String>,
+	continue_label: String,
+	break_label: String,
+}
+
+fn lookup_loop<'a>(
+	loop_stack: &'a [LoopLabels],
+	keyword: &str,
+	target: Option<types::LoopTarget>,
+) -> Result<&'a LoopLabels, String> {
+	if loop_stack.is_empty() {
+		return Err(format!("`{keyword}` may only be used inside of a loop"));
+	}
+
+	match target {
+		None => Ok(&loop_stack[loop_stack.len() - 1]),
+		Some(types::LoopTarget::Depth(depth)) => {
+			if depth == 0 || depth > loop_stack.len() {
+				return Err(format!(
+					"`{keyword} {depth}` does not refer to an enclosing loop"
+				));
+			}
+			Ok(&loop_stack[loop_stack.len() - depth])
+		}
+		Some(types::LoopTarget::Label(name)) => {
+			for i in loop_stack.iter().rev() {
+				if i.name.as_ref() == Some(&name) {
+					return Ok(i);
+				}
+			}
+			Err(format!("No enclosing loop is labeled {name}"))
+		}
+	}
+}
+
 fn compile_environment<W: Write>(
 	this_name: &str,
 	env: types::Environment,
 	environment_table: &EnvironmentTable,
+	type_table: &TypeTable,
+	symbols: &SymbolTable,
 	output: &mut W,
 ) -> Result<Environment, CompilerError> {
 	let mut compiled_env = Environment {
 		name: String::from(this_name),
 		definitions: HashMap::<String, types::Definition>::new(),
 		pool: 0,
+		symbols: SymbolTable::default(),
+		default_integer: Primative::default_integer(),
+		pointer: Primative::pointer(),
+		variables: Vec::new(),
 	};
 
 	let mut bytecode_index: u8 = 0;
+	// Types set by this environment take precedence over those of any environment it uses.
+	let mut has_default_integer = false;
+	let mut has_pointer = false;
 
 	for i in env.contents {
+		let (start, end) = (i.start, i.end);
+		let statement_error = |msg: String| -> CompilerError {
+			CompilerError {
+				start: Some(start),
+				end: Some(end),
+				msg,
+			}
+		};
+
 		match i.t {
 			StatementType::Use(name) => {
 				let other_env = match environment_table.get(&name) {
@@ -511,7 +996,7 @@ fn compile_environment<W: Write>(
 				let mut greatest_bytecode = bytecode_index;
 
 				for (def_name, def) in &other_env.definitions {
-					if compiled_env.definitions.get(def_name).is_some() {
+					if compiled_env.definitions.contains_key(def_name) {
 						eprintln!(
 							"WARN: duplicate definition of {def_name} inside `use` statement."
 						);
@@ -537,9 +1022,29 @@ fn compile_environment<W: Write>(
 				}
 
 				bytecode_index = greatest_bytecode;
+				compiled_env.symbols = compiled_env.symbols.merge(&other_env.symbols);
+				if !has_default_integer {
+					compiled_env.default_integer = other_env.default_integer;
+				}
+				if !has_pointer {
+					compiled_env.pointer = other_env.pointer;
+				}
+
+				// Shared variables keep their order, so that they have the same offsets as in the used environment.
+				// This is only possible if nothing has been reserved before them.
+				if !other_env.variables.is_empty() && !compiled_env.variables.is_empty() {
+					return Err(statement_error(format!(
+						"`use {name}` must come before any other shared variables, so that its variables keep their offsets"
+					)));
+				}
+				for (variable_name, t) in &other_env.variables {
+					compiled_env
+						.define_variable(variable_name, t.clone())
+						.map_err(statement_error)?;
+				}
 			}
 			StatementType::Definition(name, mut def) => {
-				if compiled_env.definitions.get(&name).is_some() {
+				if compiled_env.definitions.contains_key(&name) {
 					eprintln!("WARN: duplicate definition of {name}");
 				}
 				if let types::Definition::Def(ref mut sub_def) = def {
@@ -553,7 +1058,7 @@ fn compile_environment<W: Write>(
 				compiled_env.definitions.insert(name.clone(), def);
 			}
 			StatementType::Pool(expression) => {
-				let pool_size = expression.eval_const()?;
+				let pool_size = symbols.merge(&compiled_env.symbols).eval(&expression)?;
 
 				compiled_env.pool = if pool_size < 0 {
 					return Err(CompilerError::from("Pool size may not be negative"));
@@ -563,549 +1068,2922 @@ fn compile_environment<W: Write>(
 					pool_size as u16
 				};
 			}
-			_ => {
-				return Err(CompilerError::from(format!(
-					"StatementType {i:?} is not allowed within environments."
-				)))
-			}
-		}
-	}
+			StatementType::DefaultInteger(ref t) | StatementType::PointerType(ref t) => {
+				let t = match type_table.lookup_type(t).map_err(statement_error)? {
+					Type::Primative(t) if !t.boolean => t,
+					t => {
+						return Err(statement_error(format!(
+							"{t} cannot be used here, only integer types are allowed"
+						)))
+					}
+				};
+
+				if matches!(i.t, StatementType::PointerType(..)) {
+					compiled_env.pointer = t;
+					has_pointer = true;
+				} else {
+					compiled_env.default_integer = t;
+					has_default_integer = true;
+				}
+			}
+			StatementType::Constant(name, t, value) => {
+				let t = type_table.lookup_type(&t).map_err(statement_error)?;
+				let value = symbols
+					.merge(&compiled_env.symbols)
+					.eval(&value)
+					.map_err(statement_error)?;
+				compiled_env
+					.symbols
+					.define_constant(&name, t, value)
+					.map_err(statement_error)?;
+				writeln!(output, "def {this_name}@{name} equ {value}")?;
+			}
+			StatementType::Declaration(ref t, ref name)
+			| StatementType::PointerDeclaration(ref t, ref name) => {
+				let mut t = type_table.lookup_type(t).map_err(statement_error)?;
+				if matches!(i.t, StatementType::PointerDeclaration(..)) {
+					t = Type::Pointer(Box::new(t), compiled_env.pointer);
+				}
+				compiled_env
+					.define_variable(name, t)
+					.map_err(statement_error)?;
+			}
+			StatementType::ArrayDeclaration(t, name, length) => {
+				let length = symbols
+					.merge(&compiled_env.symbols)
+					.eval(&length)
+					.map_err(statement_error)?;
+				if !(1..=255).contains(&length) {
+					return Err(statement_error(String::from(
+						"Array length must be between 1 and 255",
+					)));
+				}
+
+				let element_type = type_table.lookup_type(&t).map_err(statement_error)?;
+				if element_type.size() as i64 * length > 255 {
+					return Err(statement_error(format!(
+						"{name} is too large; shared variables are limited to 256 bytes"
+					)));
+				}
+
+				compiled_env
+					.define_variable(&name, Type::Array(Box::new(element_type), length as u8))
+					.map_err(statement_error)?;
+			}
+			_ => {
+				return Err(CompilerError::from(format!(
+					"StatementType {i:?} is not allowed within environments."
+				)))
+			}
+		}
+	}
+
+	let mut offset = 0;
+	for (name, t) in &compiled_env.variables {
+		writeln!(output, "def {this_name}@{name} equ {offset}")?;
+		offset += t.size() as u16;
+	}
 
 	Ok(compiled_env)
 }
 
-/// Compiles an Rpn tree, returning a variable containing the final result.
-fn compile_expression<W: Write>(
-	rpn: Rpn,
+/// Converts a string into a list of `db` operands, ending with a terminator.
+/// If no charmap has been declared, printable characters are quoted so that RGBDS's charmap applies to them.
+fn string_bytes(text: &str, symbols: &SymbolTable) -> String {
+	let mut operands = Vec::<String>::new();
+
+	if !symbols.charmap.is_empty() {
+		for byte in symbols.encode(text) {
+			operands.push(format!("${byte:02X}"));
+		}
+		operands.push(String::from("0"));
+		return operands.join(", ");
+	}
+
+	let mut quoted = String::new();
+
+	for c in text.chars() {
+		if (' '..='~').contains(&c) || (c as u32) > 0xFF {
+			// These characters have special meanings within RGBDS strings.
+			if matches!(c, '"' | '\\' | '{' | '}') {
+				quoted.push('\\');
+			}
+			quoted.push(c);
+		} else {
+			if !quoted.is_empty() {
+				operands.push(format!("\"{quoted}\""));
+				quoted.clear();
+			}
+			operands.push(format!("${:02X}", c as u32));
+		}
+	}
+
+	if !quoted.is_empty() {
+		operands.push(format!("\"{quoted}\""));
+	}
+	operands.push(String::from("0"));
+	operands.join(", ")
+}
+
+/// Splits a constant into a little-endian list of `size` bytes.
+fn constant_bytes(value: &str, size: u8) -> String {
+	match size {
+		1 => value.to_string(),
+		2 => format!("{value} & $FF, {value} >> 8"),
+		3 => format!("{value} & $FF, ({value} >> 8) & $FF, ({value} >> 16) & $FF"),
+		4 => format!(
+			"{value} & $FF, ({value} >> 8) & $FF, ({value} >> 16) & $FF, ({value} >> 24) & $FF"
+		),
+		_ => panic!("Invalid size {size}, only up to 32 bits are supported"),
+	}
+}
+
+/// Puts a constant expression into a variable of type `t`.
+/// Wide values use the environment's `put` bytecode for that type if it exists, or are put one byte at a time.
+fn compile_put<W: Write>(
+	dest: u8,
+	value: &str,
+	t: Primative,
 	env: &Environment,
-	type_table: &TypeTable,
-	vtable: &mut VariableTable,
-	str_table: &mut Vec<String>,
 	output: &mut W,
-) -> Result<Option<u8>, CompilerError> {
-	fn binary_operation<W: Write>(
-		l: Rpn,
-		op: &str,
-		r: Rpn,
-		env: &Environment,
-		type_table: &TypeTable,
-		vtable: &mut VariableTable,
-		str_table: &mut Vec<String>,
-		output: &mut W,
-	) -> Result<Option<u8>, CompilerError> {
-		let l = compile_expression(l, env, type_table, vtable, str_table, output)?
-			.ok_or(String::from("Expression has no return value"))?;
-		let r = compile_expression(r, env, type_table, vtable, str_table, output)?
-			.ok_or(String::from("Expression has no return value"))?;
-
-		let result_type = Primative::from(vtable.type_of(l), vtable.type_of(r));
-		let result = vtable.alloc(Type::Primative(result_type))?;
-		// TODO: make opcodes consider operation size.
+) -> Result<(), CompilerError> {
+	let put = t.opcode("put");
 
+	if t.size == 1 || env.lookup(&put).is_ok() {
+		// put (dest), value
 		writeln!(
 			output,
-			"\tdb {}, {l}, {r}, {result}",
-			env.expand(&format!("{op}_{result_type}"))?
+			"\tdb {}, {dest}, {}",
+			env.expand(&put)?,
+			constant_bytes(value, t.size)
 		)?;
-
-		vtable.autofree(l);
-		vtable.autofree(r);
-
-		Ok(Some(result))
+	} else {
+		for i in 0..t.size {
+			// Numbers are split up here, while symbols are left for RGBDS to split.
+			let byte = match value.parse::<i64>() {
+				Ok(value) => ((value >> (i * 8)) & 0xFF).to_string(),
+				Err(..) if i == 0 => format!("{value} & $FF"),
+				Err(..) => format!("({value} >> {}) & $FF", i * 8),
+			};
+			writeln!(
+				output,
+				"\tdb {}, {dest} + {i}, {byte}",
+				env.expand("put_u8")?
+			)?;
+		}
 	}
 
-	fn compile_arguments<W: Write>(
-		def_args: &Vec<types::DefinitionParam>,
-		args: &[Rpn],
-		return_id: Option<u8>,
-		env: &Environment,
-		type_table: &TypeTable,
-		vtable: &mut VariableTable,
-		str_table: &mut Vec<String>,
-		output: &mut W,
-	) -> Result<Vec<String>, CompilerError> {
-		let mut index = 0;
-		let mut arg_ids = Vec::<String>::new();
-		let mut to_free = Vec::<u8>::new();
+	Ok(())
+}
 
-		for i in def_args {
-			match i {
-				types::DefinitionParam::Type(t) => {
-					let this_arg = compile_expression(
-						args[index].clone(),
-						env,
-						type_table,
-						vtable,
-						str_table,
-						output,
-					)?
-					.ok_or(String::from("Expression has no return value"))?;
+/// Puts a constant into a new variable of type `t`, which must be able to hold it.
+fn compile_literal<W: Write>(
+	value: i64,
+	t: Primative,
+	env: &Environment,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<u8, CompilerError> {
+	t.check(value)?;
 
-					if let Type::Primative(t) = type_table.lookup_type(t)? {
-						if t != vtable.type_of(this_arg) {
-							eprintln!("WARN: argument type does not match definition");
-						}
-					}
+	let result = vtable.alloc(Type::Primative(t))?;
+	compile_put(result, &value.to_string(), t, env, output)?;
 
-					arg_ids.push(this_arg.to_string());
-					// Free this temporary once all arguments are processed.
-					to_free.push(this_arg);
-					index += 1;
-				}
-				types::DefinitionParam::Const(t) => {
-					if let Type::Primative(t) = type_table.lookup_type(t)? {
-						match &args[index] {
-							Rpn::Signed(value) => match t.size {
-								1 => arg_ids.push(value.to_string()),
-								2 => {
-									arg_ids.push(format!("{value} & $FF, {value} >> 8"));
-								}
-								3 => {
-									arg_ids.push(format!("{value} & $FF, ({value} >> 8) & $FF, ({value} >> 16) & $FF"));
-								}
-								4 => {
-									arg_ids.push(format!("{value} & $FF, ({value} >> 8) & $FF, ({value} >> 16) & $FF, ({value} >> 24) & $FF"));
-								}
-								_ => panic!(
-									"Invalid size {}, only up to 32 bits are supported",
-									t.size
-								),
-							},
-							Rpn::String(text) => {
-								if t.size != 2 {
-									return Err(CompilerError::from("A string must be 16-bit"));
-								}
+	Ok(result)
+}
 
-								let value = format!(".__string{}", str_table.len());
-								str_table.push(text.clone());
-								arg_ids.push(format!("LOW({value}), HIGH({value})"));
-							}
-							Rpn::Variable(value) => match t.size {
-								1 => arg_ids.push(value.to_string()),
-								2 => {
-									arg_ids.push(format!("{value} & $FF, {value} >> 8"));
-								}
-								3 => {
-									arg_ids.push(format!("{value} & $FF, ({value} >> 8) & $FF, ({value} >> 16) & $FF"));
-								}
-								4 => {
-									arg_ids.push(format!("{value} & $FF, ({value} >> 8) & $FF, ({value} >> 16) & $FF, ({value} >> 24) & $FF"));
-								}
-								_ => panic!(
-									"Invalid size {}, only up to 32 bits are supported",
-									t.size
-								),
-							},
-							_ => return Err(CompilerError::from("Expression must be constant")),
-						}
-					} else {
-						return Err(CompilerError::from("Constant arguments may not be structs"));
-					}
-					index += 1;
-				}
-				types::DefinitionParam::Return(..) => {
-					arg_ids.push(return_id.unwrap().to_string());
-				}
-			}
-		}
+/// Copies a primative, one byte at a time if the environment has no `mov` bytecode for its type.
+fn compile_move<W: Write>(
+	dest: u8,
+	source: u8,
+	t: Primative,
+	env: &Environment,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let mov = t.opcode("mov");
 
-		for i in to_free {
-			vtable.autofree(i);
+	if t.size == 1 || env.lookup(&mov).is_ok() {
+		writeln!(output, "\tdb {}, {dest}, {source}", env.expand(&mov)?)?;
+	} else {
+		for i in 0..t.size {
+			writeln!(
+				output,
+				"\tdb {}, {dest} + {i}, {source} + {i}",
+				env.expand("mov_u8")?
+			)?;
 		}
+	}
 
-		Ok(arg_ids)
+	Ok(())
+}
+
+/// Performs a binary operation on two variables of type `t`, such as `add`.
+/// If the environment has no bytecode for this width, the operation is built from 8-bit bytecode instead,
+/// carrying between bytes where needed. Comparisons produce a bool, and everything else produces a `t`.
+#[allow(clippy::too_many_arguments)]
+fn compile_operation<W: Write>(
+	op: &str,
+	l: u8,
+	r: u8,
+	result: u8,
+	t: Primative,
+	env: &Environment,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let opcode = t.opcode(op);
+
+	if t.size == 1 || env.lookup(&opcode).is_ok() {
+		writeln!(output, "\tdb {}, {l}, {r}, {result}", env.expand(&opcode)?)?;
+		return Ok(());
 	}
 
-	fn validate_args(
-		args: &Vec<types::DefinitionParam>,
-		type_table: &TypeTable,
-		vtable: &mut VariableTable,
-	) -> Result<(usize, Option<u8>), CompilerError> {
-		let mut def_arg_count = 0;
-		let mut return_id: Option<u8> = None;
+	let byte = Primative::byte();
+	let last = t.size - 1;
+	let is_comparison = matches!(
+		op,
+		"equ" | "nequ" | "lt" | "gt" | "lte" | "gte" | "land" | "lor"
+	);
+
+	// Bytes of the operands are read after earlier bytes of the result are written,
+	// so the result may only share a variable with an operand through a copy.
+	let dest = if result == l || result == r {
+		vtable.alloc(Type::Primative(if is_comparison {
+			Primative::boolean()
+		} else {
+			t
+		}))?
+	} else {
+		result
+	};
+	let partial = vtable.alloc(Type::Primative(byte))?;
+	let scratch = vtable.alloc(Type::Primative(byte))?;
 
-		for i in args {
-			match i {
-				types::DefinitionParam::Type(..) | types::DefinitionParam::Const(..) => {
-					def_arg_count += 1;
-				}
-				types::DefinitionParam::Return(t) => {
-					if return_id.is_some() {
-						return Err(CompilerError::from(
-							"A function may only have one return value",
-						));
-					}
-					return_id = Some(vtable.alloc(type_table.lookup_type(t)?)?);
-				}
+	match op {
+		"band" | "bxor" | "bor" | "xor" => {
+			for i in 0..t.size {
+				writeln!(
+					output,
+					"\tdb {}, {l} + {i}, {r} + {i}, {dest} + {i}",
+					env.expand(&byte.opcode(op))?
+				)?;
 			}
 		}
+		// Each byte carries (or borrows) into the next if it wrapped around.
+		"add" | "sub" => {
+			let carry = vtable.alloc(Type::Primative(byte))?;
+			let add = op == "add";
+			let op = byte.opcode(op);
+
+			writeln!(output, "\tdb {}, {l}, {r}, {dest}", env.expand(&op)?)?;
+			if add {
+				writeln!(
+					output,
+					"\tdb {}, {dest}, {l}, {carry}",
+					env.expand("lt_u8")?
+				)?;
+			} else {
+				writeln!(output, "\tdb {}, {l}, {r}, {carry}", env.expand("lt_u8")?)?;
+			}
 
-		Ok((def_arg_count, return_id))
-	}
-
-	match rpn {
-		Rpn::Variable(name) => {
-			match vtable.lookup(&name) {
-				Ok(i) => Ok(Some(i)),
-				Err(..) => {
-					let result_type = Primative::default_integer();
-					let result = vtable.alloc(Type::Primative(result_type))?;
-					// put (result), value
+			for i in 1..t.size {
+				writeln!(
+					output,
+					"\tdb {}, {l} + {i}, {r} + {i}, {partial}",
+					env.expand(&op)?
+				)?;
+				if i != last {
+					if add {
+						writeln!(
+							output,
+							"\tdb {}, {partial}, {l} + {i}, {scratch}",
+							env.expand("lt_u8")?
+						)?;
+					} else {
+						writeln!(
+							output,
+							"\tdb {}, {l} + {i}, {r} + {i}, {scratch}",
+							env.expand("lt_u8")?
+						)?;
+					}
+				}
+				writeln!(
+					output,
+					"\tdb {}, {partial}, {carry}, {dest} + {i}",
+					env.expand(&op)?
+				)?;
+				if i != last {
+					if add {
+						writeln!(
+							output,
+							"\tdb {}, {dest} + {i}, {partial}, {partial}",
+							env.expand("lt_u8")?
+						)?;
+					} else {
+						writeln!(
+							output,
+							"\tdb {}, {partial}, {carry}, {partial}",
+							env.expand("lt_u8")?
+						)?;
+					}
 					writeln!(
 						output,
-						"\tdb {}, {result}, {name}",
-						env.expand(&format!("put_{result_type}"))?
+						"\tdb {}, {partial}, {scratch}, {carry}",
+						env.expand("lor_u8")?
 					)?;
-					Ok(Some(result))
 				}
 			}
+
+			vtable.free(carry);
 		}
-		Rpn::Address(name) => {
-			match vtable.lookup(&name) {
-				Ok(..) => Err(CompilerError::from(
-					"Cannot take the address of a local variable!",
-				)),
-				Err(..) => {
-					let result_type = Primative::pointer();
-					let result = vtable.alloc(Type::Primative(result_type))?;
-					// put (result), value
-					writeln!(
-						output,
-						"\tdb {}, {result}, {name} & $FF",
-						env.expand("put_u8")?
-					)?;
-					writeln!(
-						output,
-						"\tdb {}, {result} + 1, {name} >> 8",
-						env.expand("put_u8")?
-					)?;
-					Ok(Some(result))
-				}
+		// Equal if every byte is equal, and not equal if any byte is not.
+		"equ" | "nequ" => {
+			let join = if op == "equ" { "land_u8" } else { "lor_u8" };
+			let op = byte.opcode(op);
+
+			writeln!(output, "\tdb {}, {l}, {r}, {dest}", env.expand(&op)?)?;
+			for i in 1..t.size {
+				writeln!(
+					output,
+					"\tdb {}, {l} + {i}, {r} + {i}, {partial}",
+					env.expand(&op)?
+				)?;
+				writeln!(
+					output,
+					"\tdb {}, {dest}, {partial}, {dest}",
+					env.expand(join)?
+				)?;
 			}
 		}
-		Rpn::Signed(value) => {
-			// The "default" type of an integer is u8 (think C's int)
-			// This is because most projects will probably only have the 8-bit bytecode installed.
-			// TODO: make the default integer type configurable
-			let result_type = Primative {
-				signed: false,
-				size: 1,
+		// Each byte decides the comparison unless it is equal, in which case the less significant bytes do.
+		// `l > r` is `r < l`, `l <= r` is `!(r < l)` and `l >= r` is `!(l < r)`.
+		"lt" | "gt" | "lte" | "gte" => {
+			let (l, r) = if matches!(op, "gt" | "lte") {
+				(r, l)
+			} else {
+				(l, r)
 			};
-			let result = vtable.alloc(Type::Primative(result_type))?;
-			// put (result), value
+
+			writeln!(output, "\tdb {}, {l}, {r}, {dest}", env.expand("lt_u8")?)?;
+			for i in 1..t.size {
+				// Only the most significant byte holds the sign.
+				let lt = Primative {
+					signed: t.signed && i == last,
+					..byte
+				}
+				.opcode("lt");
+				writeln!(
+					output,
+					"\tdb {}, {l} + {i}, {r} + {i}, {partial}",
+					env.expand(&lt)?
+				)?;
+				writeln!(
+					output,
+					"\tdb {}, {l} + {i}, {r} + {i}, {scratch}",
+					env.expand("equ_u8")?
+				)?;
+				writeln!(
+					output,
+					"\tdb {}, {scratch}, {dest}, {dest}",
+					env.expand("land_u8")?
+				)?;
+				writeln!(
+					output,
+					"\tdb {}, {partial}, {dest}, {dest}",
+					env.expand("lor_u8")?
+				)?;
+			}
+
+			if matches!(op, "lte" | "gte") {
+				writeln!(output, "\tdb {}, {partial}, 0", env.expand("put_u8")?)?;
+				writeln!(
+					output,
+					"\tdb {}, {dest}, {partial}, {dest}",
+					env.expand("equ_u8")?
+				)?;
+			}
+		}
+		"land" | "lor" => {
+			let zero = compile_literal(0, t, env, vtable, output)?;
+			compile_operation("nequ", l, zero, partial, t, env, vtable, output)?;
+			compile_operation("nequ", r, zero, scratch, t, env, vtable, output)?;
 			writeln!(
 				output,
-				"\tdb {}, {result}, {value}",
-				env.expand(&format!("put_{result_type}"))?
+				"\tdb {}, {partial}, {scratch}, {dest}",
+				env.expand(&byte.opcode(op))?
 			)?;
-			Ok(Some(result))
+			vtable.free(zero);
 		}
-		Rpn::String(string) => {
-			let result_type = Primative {
-				signed: false,
-				size: 2,
-			};
-			let result = vtable.alloc(Type::Primative(result_type))?;
-			let value = format!(".__string{}", str_table.len());
-			// TODO: make this a 16-bit put
-			writeln!(
+		_ => {
+			return Err(CompilerError::from(format!(
+				"{opcode} is not defined, and cannot be built from 8-bit bytecode"
+			)));
+		}
+	}
+
+	vtable.free(scratch);
+	vtable.free(partial);
+
+	if dest != result {
+		compile_move(
+			result,
+			dest,
+			if is_comparison {
+				Primative::boolean()
+			} else {
+				t
+			},
+			env,
+			output,
+		)?;
+		vtable.free(dest);
+	}
+
+	Ok(())
+}
+
+/// Shifts `dest` left by one bit, one byte at a time.
+/// `carry` holds the bit to shift in (0 or 1), and is left holding the bit that was shifted out.
+fn compile_shift_left_once<W: Write>(
+	dest: u8,
+	t: Primative,
+	carry: u8,
+	env: &Environment,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let byte = Primative::byte();
+	let next = vtable.alloc(Type::Primative(byte))?;
+	let scratch = vtable.alloc(Type::Primative(byte))?;
+
+	// Each byte is doubled, and carries its top bit into the next byte up.
+	for i in 0..t.size {
+		writeln!(
+			output,
+			"\tdb {}, {dest} + {i}, {dest} + {i}, {next}",
+			env.expand("add_u8")?
+		)?;
+		writeln!(
+			output,
+			"\tdb {}, {next}, {dest} + {i}, {scratch}",
+			env.expand("lt_u8")?
+		)?;
+		writeln!(
+			output,
+			"\tdb {}, {next}, {carry}, {dest} + {i}",
+			env.expand("add_u8")?
+		)?;
+		writeln!(output, "\tdb {}, {carry}, {scratch}", env.expand("mov_u8")?)?;
+	}
+
+	vtable.free(scratch);
+	vtable.free(next);
+	Ok(())
+}
+
+/// Shifts `dest` right by one bit, one byte at a time.
+/// `carry` holds the bit to shift in at the top (0 or $80), and is overwritten.
+fn compile_shift_right_once<W: Write>(
+	dest: u8,
+	t: Primative,
+	carry: u8,
+	env: &Environment,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let byte = Primative::byte();
+	let next = vtable.alloc(Type::Primative(byte))?;
+	let top = compile_literal(0x80, byte, env, vtable, output)?;
+	let zero = compile_literal(0, byte, env, vtable, output)?;
+	let one = compile_literal(1, byte, env, vtable, output)?;
+
+	// Each byte is halved, and carries its bottom bit into the top of the next byte down.
+	for i in (0..t.size).rev() {
+		if i != 0 {
+			writeln!(
+				output,
+				"\tdb {}, {dest} + {i}, {one}, {next}",
+				env.expand("band_u8")?
+			)?;
+			writeln!(
+				output,
+				"\tdb {}, {zero}, {next}, {next}",
+				env.expand("sub_u8")?
+			)?;
+			writeln!(
+				output,
+				"\tdb {}, {next}, {top}, {next}",
+				env.expand("band_u8")?
+			)?;
+		}
+		writeln!(
+			output,
+			"\tdb {}, {dest} + {i}, {one}, {dest} + {i}",
+			env.expand("shr_u8")?
+		)?;
+		writeln!(
+			output,
+			"\tdb {}, {dest} + {i}, {carry}, {dest} + {i}",
+			env.expand("add_u8")?
+		)?;
+		if i != 0 {
+			writeln!(output, "\tdb {}, {carry}, {next}", env.expand("mov_u8")?)?;
+		}
+	}
+
+	vtable.free(one);
+	vtable.free(zero);
+	vtable.free(top);
+	vtable.free(next);
+	Ok(())
+}
+
+/// Negates `dest`, a variable of type `t`, if `flag` is nonzero.
+fn compile_negate_if<W: Write>(
+	flag: u8,
+	dest: u8,
+	t: Primative,
+	env: &Environment,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let l = *label_index;
+	*label_index += 1;
+
+	writeln!(
+		output,
+		"\tdb {}, {flag}, LOW(.__negate{l}), HIGH(.__negate{l})",
+		env.expand("jmp_if_false")?
+	)?;
+	let zero = compile_literal(0, t, env, vtable, output)?;
+	compile_operation("sub", zero, dest, dest, t, env, vtable, output)?;
+	vtable.free(zero);
+	writeln!(output, ".__negate{l}")?;
+
+	Ok(())
+}
+
+/// Shifts a variable of type `t` left or right by `r`, which is of type `t` as well.
+/// If the environment has no shift bytecode for this width, the value is shifted one bit at a time in a loop,
+/// using only the low byte of `r` as the count. Left shifts are built from `add_u8`, and right shifts need `shr_u8`.
+#[allow(clippy::too_many_arguments)]
+fn compile_shift<W: Write>(
+	op: &str,
+	l: u8,
+	r: u8,
+	result: u8,
+	t: Primative,
+	env: &Environment,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	if t.size == 1 || env.lookup(&t.opcode(op)).is_ok() {
+		return compile_operation(op, l, r, result, t, env, vtable, output);
+	}
+
+	let byte = Primative::byte();
+	let index = *label_index;
+	*label_index += 1;
+
+	let dest = vtable.alloc(Type::Primative(t))?;
+	let count = vtable.alloc(Type::Primative(byte))?;
+	let carry = vtable.alloc(Type::Primative(byte))?;
+	let zero = compile_literal(0, byte, env, vtable, output)?;
+	let one = compile_literal(1, byte, env, vtable, output)?;
+
+	compile_move(dest, l, t, env, output)?;
+	writeln!(output, "\tdb {}, {count}, {r}", env.expand("mov_u8")?)?;
+	writeln!(output, ".__shift{index}")?;
+	writeln!(
+		output,
+		"\tdb {}, {count}, {zero}, {carry}",
+		env.expand("nequ_u8")?
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, {carry}, LOW(.__shift_end{index}), HIGH(.__shift_end{index})",
+		env.expand("jmp_if_false")?
+	)?;
+
+	if op == "shl" {
+		writeln!(output, "\tdb {}, {carry}, 0", env.expand("put_u8")?)?;
+		compile_shift_left_once(dest, t, carry, env, vtable, output)?;
+	} else {
+		// Signed values keep their sign bit.
+		if t.signed {
+			writeln!(output, "\tdb {}, {carry}, $80", env.expand("put_u8")?)?;
+			writeln!(
+				output,
+				"\tdb {}, {dest} + {}, {carry}, {carry}",
+				env.expand("band_u8")?,
+				t.size - 1
+			)?;
+		} else {
+			writeln!(output, "\tdb {}, {carry}, 0", env.expand("put_u8")?)?;
+		}
+		compile_shift_right_once(dest, t, carry, env, vtable, output)?;
+	}
+
+	writeln!(
+		output,
+		"\tdb {}, {count}, {one}, {count}",
+		env.expand("sub_u8")?
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, LOW(.__shift{index}), HIGH(.__shift{index})",
+		env.expand("jmp")?
+	)?;
+	writeln!(output, ".__shift_end{index}")?;
+	compile_move(result, dest, t, env, output)?;
+
+	vtable.free(one);
+	vtable.free(zero);
+	vtable.free(carry);
+	vtable.free(count);
+	vtable.free(dest);
+
+	Ok(())
+}
+
+/// Multiplies two variables of type `t`.
+/// If the environment has no `mul` bytecode for this width, the product is built up one bit of `r` at a time,
+/// from the most significant bit down, by doubling it and adding `l` for each set bit.
+/// The product's low bits do not depend on whether it is signed, so this works for signed types as well.
+#[allow(clippy::too_many_arguments)]
+fn compile_multiply<W: Write>(
+	l: u8,
+	r: u8,
+	result: u8,
+	t: Primative,
+	env: &Environment,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	if t.size == 1 || env.lookup(&t.opcode("mul")).is_ok() {
+		return compile_operation("mul", l, r, result, t, env, vtable, output);
+	}
+
+	let byte = Primative::byte();
+	let index = *label_index;
+	*label_index += 1;
+
+	let product = vtable.alloc(Type::Primative(t))?;
+	let multiplier = vtable.alloc(Type::Primative(t))?;
+	let count = vtable.alloc(Type::Primative(byte))?;
+	let carry = vtable.alloc(Type::Primative(byte))?;
+	let zero = compile_literal(0, byte, env, vtable, output)?;
+	let one = compile_literal(1, byte, env, vtable, output)?;
+
+	compile_put(product, "0", t, env, output)?;
+	compile_move(multiplier, r, t, env, output)?;
+	writeln!(
+		output,
+		"\tdb {}, {count}, {}",
+		env.expand("put_u8")?,
+		t.size * 8
+	)?;
+	writeln!(output, ".__multiply{index}")?;
+	writeln!(
+		output,
+		"\tdb {}, {count}, {zero}, {carry}",
+		env.expand("nequ_u8")?
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, {carry}, LOW(.__multiply_end{index}), HIGH(.__multiply_end{index})",
+		env.expand("jmp_if_false")?
+	)?;
+	writeln!(output, "\tdb {}, {carry}, 0", env.expand("put_u8")?)?;
+	compile_shift_left_once(product, t, carry, env, vtable, output)?;
+	writeln!(output, "\tdb {}, {carry}, 0", env.expand("put_u8")?)?;
+	compile_shift_left_once(multiplier, t, carry, env, vtable, output)?;
+	writeln!(
+		output,
+		"\tdb {}, {carry}, LOW(.__multiply_next{index}), HIGH(.__multiply_next{index})",
+		env.expand("jmp_if_false")?
+	)?;
+	compile_operation("add", product, l, product, t, env, vtable, output)?;
+	writeln!(output, ".__multiply_next{index}")?;
+	writeln!(
+		output,
+		"\tdb {}, {count}, {one}, {count}",
+		env.expand("sub_u8")?
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, LOW(.__multiply{index}), HIGH(.__multiply{index})",
+		env.expand("jmp")?
+	)?;
+	writeln!(output, ".__multiply_end{index}")?;
+	compile_move(result, product, t, env, output)?;
+
+	vtable.free(one);
+	vtable.free(zero);
+	vtable.free(carry);
+	vtable.free(count);
+	vtable.free(multiplier);
+	vtable.free(product);
+
+	Ok(())
+}
+
+/// Divides two variables of type `t`, producing the quotient for `div` or the remainder for `mod`.
+/// If the environment has no bytecode for this width, this is long division: each bit of `l` is shifted into the remainder,
+/// from the most significant bit down, and `r` is subtracted from the remainder whenever it fits.
+/// Signed values are divided by their magnitudes. The quotient is then negative if exactly one operand was,
+/// and the remainder has the sign of `l`, as in C.
+#[allow(clippy::too_many_arguments)]
+fn compile_divide<W: Write>(
+	op: &str,
+	l: u8,
+	r: u8,
+	result: u8,
+	t: Primative,
+	env: &Environment,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	if t.size == 1 || env.lookup(&t.opcode(op)).is_ok() {
+		return compile_operation(op, l, r, result, t, env, vtable, output);
+	}
+
+	let byte = Primative::byte();
+	let unsigned = Primative { signed: false, ..t };
+	let last = t.size - 1;
+	let index = *label_index;
+	*label_index += 1;
+
+	// The dividend is shifted out of `quotient` as the quotient is shifted in.
+	let quotient = vtable.alloc(Type::Primative(t))?;
+	let divisor = vtable.alloc(Type::Primative(t))?;
+	let remainder = vtable.alloc(Type::Primative(t))?;
+	let count = vtable.alloc(Type::Primative(byte))?;
+	let carry = vtable.alloc(Type::Primative(byte))?;
+	let flag = vtable.alloc(Type::Primative(byte))?;
+	let zero = compile_literal(0, byte, env, vtable, output)?;
+	let one = compile_literal(1, byte, env, vtable, output)?;
+
+	compile_move(quotient, l, t, env, output)?;
+	compile_move(divisor, r, t, env, output)?;
+
+	let signs = if t.signed {
+		let negative_l = vtable.alloc(Type::Primative(byte))?;
+		let negative_r = vtable.alloc(Type::Primative(byte))?;
+		writeln!(output, "\tdb {}, {flag}, $80", env.expand("put_u8")?)?;
+		writeln!(
+			output,
+			"\tdb {}, {quotient} + {last}, {flag}, {negative_l}",
+			env.expand("band_u8")?
+		)?;
+		writeln!(
+			output,
+			"\tdb {}, {divisor} + {last}, {flag}, {negative_r}",
+			env.expand("band_u8")?
+		)?;
+		compile_negate_if(
+			negative_l,
+			quotient,
+			unsigned,
+			env,
+			label_index,
+			vtable,
+			output,
+		)?;
+		compile_negate_if(
+			negative_r,
+			divisor,
+			unsigned,
+			env,
+			label_index,
+			vtable,
+			output,
+		)?;
+		Some((negative_l, negative_r))
+	} else {
+		None
+	};
+
+	compile_put(remainder, "0", unsigned, env, output)?;
+	writeln!(
+		output,
+		"\tdb {}, {count}, {}",
+		env.expand("put_u8")?,
+		t.size * 8
+	)?;
+	writeln!(output, ".__divide{index}")?;
+	writeln!(
+		output,
+		"\tdb {}, {count}, {zero}, {flag}",
+		env.expand("nequ_u8")?
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, {flag}, LOW(.__divide_end{index}), HIGH(.__divide_end{index})",
+		env.expand("jmp_if_false")?
+	)?;
+	writeln!(output, "\tdb {}, {carry}, 0", env.expand("put_u8")?)?;
+	compile_shift_left_once(quotient, unsigned, carry, env, vtable, output)?;
+	compile_shift_left_once(remainder, unsigned, carry, env, vtable, output)?;
+	// The divisor fits if the remainder overflowed while shifting, or is otherwise at least the divisor.
+	compile_operation(
+		"lt", remainder, divisor, flag, unsigned, env, vtable, output,
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, {flag}, {carry}, {flag}",
+		env.expand("gt_u8")?
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, {flag}, LOW(.__divide_next{index}), HIGH(.__divide_next{index})",
+		env.expand("jmp_if_true")?
+	)?;
+	compile_operation(
+		"sub", remainder, divisor, remainder, unsigned, env, vtable, output,
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, {quotient}, {one}, {quotient}",
+		env.expand("add_u8")?
+	)?;
+	writeln!(output, ".__divide_next{index}")?;
+	writeln!(
+		output,
+		"\tdb {}, {count}, {one}, {count}",
+		env.expand("sub_u8")?
+	)?;
+	writeln!(
+		output,
+		"\tdb {}, LOW(.__divide{index}), HIGH(.__divide{index})",
+		env.expand("jmp")?
+	)?;
+	writeln!(output, ".__divide_end{index}")?;
+
+	let answer = if op == "div" { quotient } else { remainder };
+	if let Some((negative_l, negative_r)) = signs {
+		if op == "div" {
+			writeln!(
+				output,
+				"\tdb {}, {negative_l}, {negative_r}, {flag}",
+				env.expand("nequ_u8")?
+			)?;
+			compile_negate_if(flag, answer, unsigned, env, label_index, vtable, output)?;
+		} else {
+			compile_negate_if(
+				negative_l,
+				answer,
+				unsigned,
+				env,
+				label_index,
+				vtable,
+				output,
+			)?;
+		}
+		vtable.free(negative_r);
+		vtable.free(negative_l);
+	}
+	compile_move(result, answer, t, env, output)?;
+
+	vtable.free(one);
+	vtable.free(zero);
+	vtable.free(flag);
+	vtable.free(carry);
+	vtable.free(count);
+	vtable.free(remainder);
+	vtable.free(divisor);
+	vtable.free(quotient);
+
+	Ok(())
+}
+
+/// Finds the address of the `i`th byte after `pointer`, for loading or storing a value one byte at a time.
+fn byte_address<W: Write>(
+	pointer: u8,
+	i: u8,
+	env: &Environment,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<u8, CompilerError> {
+	let pointer_type = vtable.type_of(pointer);
+	let offset = compile_literal(i as i64, pointer_type, env, vtable, output)?;
+	let address = vtable.alloc(Type::Primative(pointer_type))?;
+	compile_operation(
+		"add",
+		pointer,
+		offset,
+		address,
+		pointer_type,
+		env,
+		vtable,
+		output,
+	)?;
+	vtable.free(offset);
+	Ok(address)
+}
+
+/// Loads a `t` from the address in `pointer`, one byte at a time if the environment has no `deref` bytecode for its type.
+fn compile_load<W: Write>(
+	dest: u8,
+	pointer: u8,
+	t: Primative,
+	env: &Environment,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let deref = t.opcode("deref");
+
+	if t.size == 1 || env.lookup(&deref).is_ok() {
+		writeln!(output, "\tdb {}, {dest}, {pointer}", env.expand(&deref)?)?;
+		return Ok(());
+	}
+
+	writeln!(
+		output,
+		"\tdb {}, {dest}, {pointer}",
+		env.expand("deref_u8")?
+	)?;
+	for i in 1..t.size {
+		let address = byte_address(pointer, i, env, vtable, output)?;
+		writeln!(
+			output,
+			"\tdb {}, {dest} + {i}, {address}",
+			env.expand("deref_u8")?
+		)?;
+		vtable.free(address);
+	}
+
+	Ok(())
+}
+
+/// Stores a `t` to the address in `pointer`, one byte at a time if the environment has no `store` bytecode for its type.
+fn compile_store<W: Write>(
+	pointer: u8,
+	source: u8,
+	t: Primative,
+	env: &Environment,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let store = t.opcode("store");
+
+	if t.size == 1 || env.lookup(&store).is_ok() {
+		// store (pointer), (value)
+		writeln!(output, "\tdb {}, {pointer}, {source}", env.expand(&store)?)?;
+		return Ok(());
+	}
+
+	writeln!(
+		output,
+		"\tdb {}, {pointer}, {source}",
+		env.expand("store_u8")?
+	)?;
+	for i in 1..t.size {
+		let address = byte_address(pointer, i, env, vtable, output)?;
+		writeln!(
+			output,
+			"\tdb {}, {address}, {source} + {i}",
+			env.expand("store_u8")?
+		)?;
+		vtable.free(address);
+	}
+
+	Ok(())
+}
+
+/// Copies a variable of any type.
+/// Structures and arrays use the environment's `copy` bytecode if it exists, or are copied one member at a time.
+fn compile_copy<W: Write>(
+	dest: u8,
+	source: u8,
+	t: &Type,
+	env: &Environment,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	match t {
+		Type::Primative(t) | Type::Enum(_, t) => compile_move(dest, source, *t, env, output)?,
+		Type::Pointer(_, t) => compile_move(dest, source, *t, env, output)?,
+		Type::Struct(..) | Type::Array(..) if env.lookup("copy").is_ok() => {
+			// copy (dest), (source), (size)
+			writeln!(
+				output,
+				"\tdb {}, {dest}, {source}, {}",
+				env.expand("copy")?,
+				t.size()
+			)?;
+		}
+		Type::Struct(_, members) => {
+			let mut offset = 0;
+			for (_, member) in members {
+				compile_copy(dest + offset, source + offset, member, env, output)?;
+				offset += member.size();
+			}
+		}
+		Type::Array(element, length) => {
+			for i in 0..*length {
+				let offset = i * element.size();
+				compile_copy(dest + offset, source + offset, element, env, output)?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Converts a variable to another primative type, returning a variable of that type.
+/// Widening uses the environment's `zext`/`sext` bytecode if it exists, or fills the upper bytes itself.
+/// Narrowing keeps the lower bytes, and warns unless the conversion is `explicit`.
+fn compile_conversion<W: Write>(
+	source: u8,
+	t: Primative,
+	explicit: bool,
+	env: &Environment,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	output: &mut W,
+) -> Result<u8, CompilerError> {
+	let source_type = vtable.type_of(source);
+
+	// Any non-zero integer is true, so it must be compared against zero rather than truncated.
+	if t.boolean && !source_type.boolean {
+		let zero = compile_literal(0, source_type, env, vtable, output)?;
+		let dest = vtable.alloc(Type::Primative(t))?;
+		compile_operation("nequ", source, zero, dest, source_type, env, vtable, output)?;
+		vtable.free(zero);
+		vtable.autofree(source);
+		return Ok(dest);
+	}
+
+	if source_type.size == t.size {
+		// Only casts care about signedness; otherwise, the operation's type decides how the bytes are treated.
+		if !explicit || source_type == t {
+			return Ok(source);
+		}
+		if vtable.is_temporary(source) {
+			*vtable.type_of_mut(source) = Type::Primative(t);
+			return Ok(source);
+		}
+		let dest = vtable.alloc(Type::Primative(t))?;
+		compile_move(dest, source, source_type, env, output)?;
+		return Ok(dest);
+	}
+
+	let dest = vtable.alloc(Type::Primative(t))?;
+
+	if source_type.size > t.size {
+		if !explicit {
+			eprintln!("WARN: implicitly narrowing a {source_type} to a {t}. Use `as {t}` if this is intended.");
+		}
+		// Values are little-endian, so the lower bytes begin at the same offset.
+		compile_move(dest, source, t, env, output)?;
+		vtable.autofree(source);
+		return Ok(dest);
+	}
+
+	let extend = format!(
+		"{}ext_{}_{t}",
+		if source_type.signed { 's' } else { 'z' },
+		source_type.integer()
+	);
+
+	if env.lookup(&extend).is_ok() {
+		writeln!(output, "\tdb {}, {dest}, {source}", env.expand(&extend)?)?;
+	} else {
+		compile_move(dest, source, source_type, env, output)?;
+		for i in source_type.size..t.size {
+			writeln!(output, "\tdb {}, {dest} + {i}, 0", env.expand("put_u8")?)?;
+		}
+
+		// Negative values need their upper bytes filled with ones instead.
+		if source_type.signed {
+			let l = *label_index;
+			*label_index += 1;
+
+			let sign = vtable.alloc(Type::Primative(Primative::byte()))?;
+			writeln!(output, "\tdb {}, {sign}, $80", env.expand("put_u8")?)?;
+			writeln!(
+				output,
+				"\tdb {}, {source} + {}, {sign}, {sign}",
+				env.expand("band_u8")?,
+				source_type.size - 1
+			)?;
+			writeln!(
+				output,
+				"\tdb {}, {sign}, LOW(.__extend{l}), HIGH(.__extend{l})",
+				env.expand("jmp_if_false")?
+			)?;
+			for i in source_type.size..t.size {
+				writeln!(output, "\tdb {}, {dest} + {i}, $FF", env.expand("put_u8")?)?;
+			}
+			writeln!(output, ".__extend{l}")?;
+			vtable.free(sign);
+		}
+	}
+
+	vtable.autofree(source);
+	Ok(dest)
+}
+
+/// Compiles an Rpn tree, returning a variable containing the final result.
+#[allow(clippy::too_many_arguments)]
+fn compile_expression<W: Write>(
+	rpn: Rpn,
+	env: &Environment,
+	type_table: &TypeTable,
+	symbols: &SymbolTable,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	str_table: &mut Vec<String>,
+	output: &mut W,
+) -> Result<Option<u8>, CompilerError> {
+	#[allow(clippy::too_many_arguments)]
+	fn binary_operation<W: Write>(
+		l: Rpn,
+		op: &str,
+		r: Rpn,
+		env: &Environment,
+		type_table: &TypeTable,
+		symbols: &SymbolTable,
+		label_index: &mut u32,
+		vtable: &mut VariableTable,
+		str_table: &mut Vec<String>,
+		output: &mut W,
+	) -> Result<Option<u8>, CompilerError> {
+		let mut operand = |rpn: Rpn, vtable: &mut VariableTable| -> Result<u8, CompilerError> {
+			Ok(compile_expression(
+				rpn,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?)
+		};
+
+		// A constant operand takes the type of the other operand, so `x + 1` is as wide as x.
+		let (l, r) = match (
+			symbols.eval_local(&l, vtable),
+			symbols.eval_local(&r, vtable),
+		) {
+			(Ok(value), Err(..)) => {
+				let r = operand(r, vtable)?;
+				let t = Primative::literal(value, vtable.type_of(r))?;
+				(compile_literal(value, t, env, vtable, output)?, r)
+			}
+			(Err(..), Ok(value)) => {
+				let l = operand(l, vtable)?;
+				let t = Primative::literal(value, vtable.type_of(l))?;
+				(l, compile_literal(value, t, env, vtable, output)?)
+			}
+			_ => {
+				let l = operand(l, vtable)?;
+				(l, operand(r, vtable)?)
+			}
+		};
+
+		let operand_type = Primative::from(vtable.type_of(l), vtable.type_of(r));
+		// Only bitwise operations keep two booleans as 0 or 1.
+		let operand_type = if matches!(op, "band" | "bxor" | "bor") {
+			operand_type
+		} else {
+			operand_type.integer()
+		};
+		let result_type = if matches!(
+			op,
+			"equ" | "nequ" | "lt" | "gt" | "lte" | "gte" | "land" | "lor"
+		) {
+			Primative::boolean()
+		} else {
+			operand_type
+		};
+		let l = compile_conversion(l, operand_type, false, env, label_index, vtable, output)?;
+		let r = compile_conversion(r, operand_type, false, env, label_index, vtable, output)?;
+		let result = vtable.alloc(Type::Primative(result_type))?;
+		match op {
+			"shl" | "shr" => compile_shift(
+				op,
+				l,
+				r,
+				result,
+				operand_type,
+				env,
+				label_index,
+				vtable,
+				output,
+			)?,
+			"mul" => {
+				compile_multiply(l, r, result, operand_type, env, label_index, vtable, output)?
+			}
+			"div" | "mod" => compile_divide(
+				op,
+				l,
+				r,
+				result,
+				operand_type,
+				env,
+				label_index,
+				vtable,
+				output,
+			)?,
+			_ => compile_operation(op, l, r, result, operand_type, env, vtable, output)?,
+		}
+
+		vtable.autofree(l);
+		vtable.autofree(r);
+
+		Ok(Some(result))
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn compile_arguments<W: Write>(
+		def_args: &Vec<types::DefinitionParam>,
+		args: &[Rpn],
+		return_id: Option<u8>,
+		env: &Environment,
+		type_table: &TypeTable,
+		symbols: &SymbolTable,
+		label_index: &mut u32,
+		vtable: &mut VariableTable,
+		str_table: &mut Vec<String>,
+		output: &mut W,
+	) -> Result<Vec<String>, CompilerError> {
+		let mut index = 0;
+		let mut arg_ids = Vec::<String>::new();
+		let mut to_free = Vec::<u8>::new();
+
+		for i in def_args {
+			match i {
+				types::DefinitionParam::Type(t) => {
+					let param_type = type_table.lookup_type(t)?;
+					let this_arg = match param_type.as_primative() {
+						Some(t) => {
+							check_enum(&param_type, &args[index], env, type_table, vtable, symbols);
+							let this_arg = compile_typed_expression(
+								args[index].clone(),
+								t,
+								env,
+								type_table,
+								symbols,
+								label_index,
+								vtable,
+								str_table,
+								output,
+							)?;
+							if t != vtable.type_of(this_arg) {
+								eprintln!("WARN: argument type does not match definition");
+							}
+							this_arg
+						}
+						None => compile_expression(
+							args[index].clone(),
+							env,
+							type_table,
+							symbols,
+							label_index,
+							vtable,
+							str_table,
+							output,
+						)?
+						.ok_or(String::from("Expression has no return value"))?,
+					};
+
+					arg_ids.push(this_arg.to_string());
+					// Free this temporary once all arguments are processed.
+					to_free.push(this_arg);
+					index += 1;
+				}
+				types::DefinitionParam::Const(t) => {
+					// Named constants are replaced by their values.
+					let arg = match symbols.eval_local(&args[index], vtable) {
+						Ok(value) => Rpn::Signed(value),
+						Err(..) => args[index].clone(),
+					};
+
+					let param_type = type_table.lookup_type(t)?;
+					if let Some(t) = param_type.as_primative() {
+						check_enum(&param_type, &args[index], env, type_table, vtable, symbols);
+						match &arg {
+							Rpn::Signed(value) => {
+								t.check(*value)?;
+								arg_ids.push(constant_bytes(&value.to_string(), t.size))
+							}
+							Rpn::String(text) => {
+								if t.size != 2 {
+									return Err(CompilerError::from("A string must be 16-bit"));
+								}
+
+								let value = format!(".__string{}", str_table.len());
+								str_table.push(text.clone());
+								arg_ids.push(format!("LOW({value}), HIGH({value})"));
+							}
+							Rpn::Variable(value) => arg_ids.push(constant_bytes(value, t.size)),
+							_ => return Err(CompilerError::from("Expression must be constant")),
+						}
+					} else {
+						return Err(CompilerError::from("Constant arguments may not be structs"));
+					}
+					index += 1;
+				}
+				types::DefinitionParam::Return(..) => {
+					arg_ids.push(return_id.unwrap().to_string());
+				}
+			}
+		}
+
+		for i in to_free {
+			vtable.autofree(i);
+		}
+
+		Ok(arg_ids)
+	}
+
+	fn validate_args(
+		args: &Vec<types::DefinitionParam>,
+		type_table: &TypeTable,
+		vtable: &mut VariableTable,
+	) -> Result<(usize, Option<u8>), CompilerError> {
+		let mut def_arg_count = 0;
+		let mut return_id: Option<u8> = None;
+
+		for i in args {
+			match i {
+				types::DefinitionParam::Type(..) | types::DefinitionParam::Const(..) => {
+					def_arg_count += 1;
+				}
+				types::DefinitionParam::Return(t) => {
+					if return_id.is_some() {
+						return Err(CompilerError::from(
+							"A function may only have one return value",
+						));
+					}
+					return_id = Some(vtable.alloc(type_table.lookup_type(t)?)?);
+				}
+			}
+		}
+
+		Ok((def_arg_count, return_id))
+	}
+
+	/// Computes the address of a member of the structure `pointer` points to.
+	fn member_address<W: Write>(
+		pointer: u8,
+		members: &str,
+		env: &Environment,
+		vtable: &mut VariableTable,
+		output: &mut W,
+	) -> Result<(u8, Type), CompilerError> {
+		let (offset, member_type) = match vtable.type_at(pointer) {
+			Type::Pointer(t, _) if matches!(**t, Type::Struct(..)) => {
+				let (offset, member_type) = t.member_path(&t.to_string(), members)?;
+				(offset, member_type.clone())
+			}
+			_ => {
+				return Err(CompilerError::from(format!(
+					"Cannot access {members} through a pointer that does not point to a structure"
+				)))
+			}
+		};
+
+		if offset == 0 {
+			return Ok((pointer, member_type));
+		}
+
+		let offset_id = compile_literal(offset as i64, env.pointer, env, vtable, output)?;
+
+		let address = vtable.alloc(Type::Pointer(Box::new(member_type.clone()), env.pointer))?;
+		compile_operation(
+			"add",
+			pointer,
+			offset_id,
+			address,
+			env.pointer,
+			env,
+			vtable,
+			output,
+		)?;
+
+		vtable.free(offset_id);
+		vtable.autofree(pointer);
+
+		Ok((address, member_type))
+	}
+
+	/// Puts the address of an extern into a new pointer to `t`.
+	fn extern_address<W: Write>(
+		address: &str,
+		t: Type,
+		env: &Environment,
+		vtable: &mut VariableTable,
+		output: &mut W,
+	) -> Result<u8, CompilerError> {
+		let pointer = vtable.alloc(Type::Pointer(Box::new(t), env.pointer))?;
+		compile_put(pointer, address, env.pointer, env, output)?;
+		Ok(pointer)
+	}
+
+	// Expressions made up entirely of constants are folded into a single value.
+	let rpn = match symbols.eval_local(&rpn, vtable) {
+		Ok(..) if matches!(rpn, Rpn::Signed(..) | Rpn::Variable(..) | Rpn::Bool(..)) => rpn,
+		Ok(value) if rpn.is_boolean() => Rpn::Bool(value != 0),
+		Ok(value) => Rpn::Signed(value),
+		_ => rpn,
+	};
+
+	match rpn {
+		Rpn::Variable(name) => match vtable.lookup(&name) {
+			Ok(i) => Ok(Some(i)),
+			Err(..) if symbols.lookup_constant(&name).is_some() => {
+				let (result_type, value) = symbols.lookup_constant(&name).unwrap();
+				Ok(Some(compile_literal(
+					value,
+					result_type,
+					env,
+					vtable,
+					output,
+				)?))
+			}
+			Err(..) if symbols.lookup_extern_constant(&name).is_some() => {
+				let result_type = symbols.lookup_extern_constant(&name).unwrap();
+				let result = vtable.alloc(Type::Primative(result_type))?;
+				compile_put(result, &name, result_type, env, output)?;
+				Ok(Some(result))
+			}
+			Err(err) => {
+				let Some((address, t)) = symbols.lookup_extern(&name)? else {
+					return Err(CompilerError::from(format!(
+						"{err}. If {name} is defined in assembly, declare it using `extern`"
+					)));
+				};
+				let load_type = match t {
+					Type::Primative(t) | Type::Enum(_, t) => t,
+					Type::Pointer(_, t) => t,
+					Type::Struct(..) | Type::Array(..) => {
+						return Err(CompilerError::from(format!("{name} is a {t} and cannot be loaded. Try working with individual members.")));
+					}
+				};
+
+				let pointer = extern_address(&address, t, env, vtable, output)?;
+				let dest = vtable.alloc(Type::Primative(load_type))?;
+				compile_load(dest, pointer, load_type, env, vtable, output)?;
+				vtable.free(pointer);
+
+				Ok(Some(dest))
+			}
+		},
+		Rpn::Index(name, index) => {
+			let (base, element_type, length) = vtable.lookup_array(&name)?;
+
+			if let Ok(index) = symbols.eval_local(&index, vtable) {
+				if index < 0 || index >= length as i64 {
+					return Err(CompilerError::from(format!(
+						"Index {index} is out of bounds for {name}, which has {length} elements"
+					)));
+				}
+				// Constant indices refer directly to the element within the pool.
+				return Ok(Some(base + index as u8 * element_type.size()));
+			}
+
+			let load_type = match element_type {
+				Type::Primative(t) | Type::Enum(_, t) => t,
+				Type::Pointer(_, t) => t,
+				Type::Struct(..) | Type::Array(..) => {
+					return Err(CompilerError::from(
+						"Arrays of structures may only be indexed by constants",
+					))
+				}
+			};
+
+			let index = compile_expression(
+				*index,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			let dest = vtable.alloc(element_type)?;
+
+			// load_index (array), (index), (result)
+			writeln!(
+				output,
+				"\tdb {}, {base}, {index}, {dest}",
+				env.expand(&load_type.opcode("load_index"))?
+			)?;
+
+			vtable.autofree(index);
+
+			Ok(Some(dest))
+		}
+		Rpn::Current => Ok(Some(vtable.current.take().ok_or(String::from(
+			"Only compound assignments may refer to the current value",
+		))?)),
+		Rpn::Address(name) => match vtable.lookup(&name) {
+			Ok(..) => Err(CompilerError::from(
+				"Cannot take the address of a local variable!",
+			)),
+			Err(err) => match symbols.lookup_extern(&name)? {
+				Some((address, t)) => Ok(Some(extern_address(&address, t, env, vtable, output)?)),
+				None => Err(CompilerError::from(format!(
+					"{err}. If {name} is defined in assembly, declare it using `extern`"
+				))),
+			},
+		},
+		Rpn::Cast(i, t) => {
+			let Some(t) = type_table.lookup_type(&t)?.as_primative() else {
+				return Err(CompilerError::from(format!(
+					"Cannot cast to {t}, only integer types may be the target of a cast"
+				)));
+			};
+			let source = compile_expression(
+				*i,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			Ok(Some(compile_conversion(
+				source,
+				t,
+				true,
+				env,
+				label_index,
+				vtable,
+				output,
+			)?))
+		}
+		Rpn::Signed(value) => {
+			// Without any context, an integer is the environment's default integer (think C's int), unless it is too large for one.
+			// This is u8 unless configured otherwise, because most projects will probably only have the 8-bit bytecode installed.
+			// Assignments, arguments and operations with another variable give a literal their type instead.
+			let result_type = Primative::literal(value, env.default_integer)?;
+			Ok(Some(compile_literal(
+				value,
+				result_type,
+				env,
+				vtable,
+				output,
+			)?))
+		}
+		Rpn::Bool(value) => Ok(Some(compile_literal(
+			value as i64,
+			Primative::boolean(),
+			env,
+			vtable,
+			output,
+		)?)),
+		Rpn::Character(text) => {
+			let value = symbols.character(&text)?;
+			compile_expression(
+				Rpn::Signed(value),
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)
+		}
+		Rpn::String(string) => {
+			let result_type = env.pointer;
+			let result = vtable.alloc(Type::Primative(result_type))?;
+			let value = format!(".__string{}", str_table.len());
+			compile_put(result, &value, result_type, env, output)?;
+			str_table.push(string);
+			Ok(Some(result))
+		}
+		Rpn::CallScript(name, args) => {
+			let script = symbols.lookup_script(&name).ok_or(format!(
+				"Script {name} not found. A script must be defined before it can be called"
+			))?;
+
+			// The callee's bytecode is run by the caller's environment, so the two must agree.
+			for (def_name, bytecode) in &script.bytecodes {
+				match env.lookup(def_name) {
+					Ok(types::Definition::Def(def)) if def.bytecode == *bytecode => {}
+					_ => {
+						return Err(CompilerError::from(format!(
+							"{name} uses environment {}, which is not compatible with {}: {def_name} differs",
+							script.environment, env.name
+						)))
+					}
+				}
+			}
+			if !env.variables.starts_with(&script.variables) {
+				return Err(CompilerError::from(format!(
+					"{name} uses environment {}, which does not share the same variables as {}",
+					script.environment, env.name
+				)));
+			}
+
+			match args.len().cmp(&script.params.len()) {
+				Ordering::Equal => {}
+				Ordering::Greater => return Err(CompilerError::from("Too many arguments")),
+				Ordering::Less => return Err(CompilerError::from("Not enough arguments")),
+			}
+
+			let call = env.expand("call_script")?;
+
+			let mut arg_ids = Vec::<u8>::new();
+			for (arg, (_, param_type)) in args.into_iter().zip(&script.params) {
+				let arg_id = match param_type {
+					Type::Primative(t) | Type::Enum(_, t) | Type::Pointer(_, t) => {
+						check_enum(param_type, &arg, env, type_table, vtable, symbols);
+						compile_typed_expression(
+							arg,
+							*t,
+							env,
+							type_table,
+							symbols,
+							label_index,
+							vtable,
+							str_table,
+							output,
+						)?
+					}
+					Type::Struct(..) | Type::Array(..) => {
+						let Rpn::Variable(arg_name) = arg else {
+							return Err(CompilerError::from(
+								"Structures may only be passed from another variable",
+							));
+						};
+						let (arg_id, arg_type) = vtable.lookup_with_type(&arg_name)?;
+						if arg_type != param_type {
+							return Err(CompilerError::from(format!(
+								"Cannot pass a {arg_type} as a {param_type}"
+							)));
+						}
+						arg_id
+					}
+				};
+				arg_ids.push(arg_id);
+			}
+
+			let result = match script.return_type {
+				Some(t) => Some(vtable.alloc(Type::Primative(t))?),
+				None => None,
+			};
+
+			// The callee's pool begins after everything the caller is using, including the arguments.
+			let frame_start = vtable.usage();
+			let frame_end = frame_start + script.peak_usage as u16;
+			if frame_end > env.pool || frame_end > 255 {
+				return Err(CompilerError::from(format!(
+					"Calling {name} needs {frame_end} bytes, more than the maximum pool size for {}: {}",
+					env.name, env.pool
+				)));
+			}
+			let frame = frame_start as u8;
+			if vtable.peak_usage < frame_end as u8 {
+				vtable.peak_usage = frame_end as u8;
+			}
+
+			// Shared variables are copied into the callee's pool, and back out once it returns.
+			let mut offset = 0;
+			for (_, t) in &script.variables {
+				compile_copy(frame + offset, offset, t, env, output)?;
+				offset += t.size();
+			}
+			for (arg_id, (param_offset, param_type)) in arg_ids.into_iter().zip(&script.params) {
+				compile_copy(frame + param_offset, arg_id, param_type, env, output)?;
+				vtable.autofree(arg_id);
+			}
+
+			writeln!(
+				output,
+				"\tdb {call}, {frame}, LOW({name}), HIGH({name}), BANK({name})"
+			)?;
+
+			let mut offset = 0;
+			for (_, t) in &script.variables {
+				compile_copy(offset, frame + offset, t, env, output)?;
+				offset += t.size();
+			}
+			if let (Some(result), Some(t)) = (result, script.return_type) {
+				compile_move(result, frame + script.return_offset, t, env, output)?;
+			}
+
+			Ok(result)
+		}
+		Rpn::Call(name, args) => match env.lookup(&name)? {
+			types::Definition::Def(def) => {
+				let (def_arg_count, return_id) = validate_args(&def.args, type_table, vtable)?;
+
+				match args.len().cmp(&def_arg_count) {
+					Ordering::Equal => {}
+					Ordering::Greater => return Err(CompilerError::from("Too many arguments")),
+					Ordering::Less => return Err(CompilerError::from("Not enough arguments")),
+				}
+
+				let arg_ids = compile_arguments(
+					&def.args,
+					&args,
+					return_id,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					vtable,
+					str_table,
+					output,
+				)?;
+
+				write!(output, "\tdb {}", env.expand(&name)?)?;
+				for i in arg_ids {
+					write!(output, ", {i}")?;
+				}
+				writeln!(output)?;
+
+				Ok(return_id)
+			}
+			types::Definition::Alias(def) => {
+				enum AliasVariant {
+					ArgId(usize),
+					ExpressionId(String),
+				}
+
+				let (def_arg_count, return_id) = validate_args(&def.args, type_table, vtable)?;
+
+				match args.len().cmp(&def_arg_count) {
+					Ordering::Equal => {}
+					Ordering::Greater => return Err(CompilerError::from("Too many arguments")),
+					Ordering::Less => return Err(CompilerError::from("Not enough arguments")),
+				}
+
+				let arg_ids = compile_arguments(
+					&def.args,
+					&args,
+					return_id,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					vtable,
+					str_table,
+					output,
+				)?;
+
+				let mut alias_ids = Vec::<AliasVariant>::new();
+
+				for i in &def.target_args {
+					match i {
+						types::AliasParam::ArgId(index) => {
+							alias_ids.push(AliasVariant::ArgId(*index))
+						}
+						types::AliasParam::Expression(rpn) => {
+							let this_arg = compile_expression(
+								rpn.clone(),
+								env,
+								type_table,
+								symbols,
+								label_index,
+								vtable,
+								str_table,
+								output,
+							)?
+							.ok_or(String::from("Expression has no return value"))?;
+							alias_ids.push(AliasVariant::ExpressionId(this_arg.to_string()));
+							vtable.autofree(this_arg);
+						}
+						types::AliasParam::Const(rpn) => match symbols
+							.eval_local(rpn, vtable)
+							.map_or(rpn.clone(), Rpn::Signed)
+						{
+							Rpn::Signed(value) => {
+								if !(-128..256).contains(&value) {
+									return Err(CompilerError::from(
+										"WARN: integer constants can only be 8 bits",
+									));
+								}
+								alias_ids.push(AliasVariant::ExpressionId(value.to_string()));
+							}
+							Rpn::String(text) => {
+								let value = format!(".__string{}", str_table.len());
+								str_table.push(text);
+								alias_ids.push(AliasVariant::ExpressionId(format!(
+									"LOW({value}), HIGH({value})"
+								)));
+							}
+							Rpn::Variable(value) => {
+								alias_ids.push(AliasVariant::ExpressionId(value));
+							}
+							_ => return Err(CompilerError::from("Expression must be constant")),
+						},
+					}
+				}
+
+				write!(output, "\tdb {}", env.expand(&name)?)?;
+				for i in alias_ids {
+					match i {
+						AliasVariant::ExpressionId(index) => write!(output, ", {index}")?,
+						AliasVariant::ArgId(index) => {
+							if index > arg_ids.len() {
+								return Err(CompilerError::from(format!(
+									"Argument ID is too large ({index})"
+								)));
+							}
+							write!(output, ", {}", arg_ids[index - 1])?;
+						}
+					}
+				}
+				writeln!(output)?;
+
+				Ok(return_id)
+			}
+			types::Definition::Macro(def) => {
+				let (def_arg_count, return_id) = validate_args(&def.args, type_table, vtable)?;
+
+				match args.len().cmp(&def_arg_count) {
+					Ordering::Equal => {}
+					Ordering::Greater => return Err(CompilerError::from("Too many arguments")),
+					Ordering::Less => return Err(CompilerError::from("Not enough arguments")),
+				}
+
+				let arg_ids = compile_arguments(
+					&def.args,
+					&args,
+					return_id,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					vtable,
+					str_table,
+					output,
+				)?;
+
+				write!(output, "\t{}", def.target)?;
+				for i in arg_ids {
+					write!(output, " {i},")?;
+				}
+				writeln!(output)?;
+
+				Ok(return_id)
+			}
+		},
+		Rpn::Negate(i) => {
+			let operand = compile_expression(
+				*i,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			let operand_type = vtable.type_of(operand).integer();
+			let zero = compile_literal(0, operand_type, env, vtable, output)?;
+			let result = vtable.alloc(Type::Primative(operand_type))?;
+			compile_operation(
+				"sub",
+				zero,
+				operand,
+				result,
+				operand_type,
+				env,
+				vtable,
+				output,
+			)?;
+
+			vtable.free(zero);
+			vtable.autofree(operand);
+
+			Ok(Some(result))
+		}
+		Rpn::Not(i) => {
+			let operand = compile_expression(
+				*i,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			let operand_type = vtable.type_of(operand).integer();
+			let result = vtable.alloc(Type::Primative(Primative::boolean()))?;
+			let lnot = operand_type.opcode("lnot");
+
+			if env.lookup(&lnot).is_ok() {
+				// lnot (operand), (result)
+				writeln!(output, "\tdb {}, {operand}, {result}", env.expand(&lnot)?)?;
+			} else {
+				let zero = compile_literal(0, operand_type, env, vtable, output)?;
+				compile_operation(
+					"equ",
+					operand,
+					zero,
+					result,
+					operand_type,
+					env,
+					vtable,
+					output,
+				)?;
+				vtable.free(zero);
+			}
+
+			vtable.autofree(operand);
+
+			Ok(Some(result))
+		}
+		Rpn::Complement(i) => {
+			let operand = compile_expression(
+				*i,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			let operand_type = vtable.type_of(operand).integer();
+			// A mask with every bit set, however wide the operand is.
+			let (_, max) = operand_type.range();
+			let mask = compile_literal(
+				if operand_type.signed { -1 } else { max },
+				operand_type,
+				env,
+				vtable,
+				output,
+			)?;
+			let result = vtable.alloc(Type::Primative(operand_type))?;
+			compile_operation(
+				"xor",
+				operand,
+				mask,
+				result,
+				operand_type,
+				env,
+				vtable,
+				output,
+			)?;
+
+			vtable.free(mask);
+			vtable.autofree(operand);
+
+			Ok(Some(result))
+		}
+		Rpn::Deref(i) => {
+			let source = compile_expression(
+				*i,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+
+			if !vtable.is_pointer(source) {
+				return Err(CompilerError::from(format!(
+					"Cannot deref a {}, expected a pointer",
+					vtable.type_at(source)
+				)));
+			}
+
+			let source_type = match vtable.type_at(source) {
+				Type::Pointer(t, _) => match **t {
+					Type::Primative(t) | Type::Enum(_, t) => t,
+					Type::Pointer(_, t) => t,
+					Type::Struct(..) | Type::Array(..) => {
+						return Err(CompilerError::from("A pointer to a structure cannot be dereferenced. Try working with individual members."));
+					}
+				},
+				_ => panic!(),
+			};
+
+			let dest = vtable.alloc(Type::Primative(source_type))?;
+			compile_load(dest, source, source_type, env, vtable, output)?;
+
+			vtable.autofree(source);
+
+			Ok(Some(dest))
+		}
+		Rpn::Mul(l, r) => binary_operation(
+			*l,
+			"mul",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::Div(l, r) => binary_operation(
+			*l,
+			"div",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::Mod(l, r) => binary_operation(
+			*l,
+			"mod",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::Add(l, r) => binary_operation(
+			*l,
+			"add",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::Sub(l, r) => binary_operation(
+			*l,
+			"sub",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::ShiftLeft(l, r) => binary_operation(
+			*l,
+			"shl",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::ShiftRight(l, r) => binary_operation(
+			*l,
+			"shr",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::BinaryAnd(l, r) => binary_operation(
+			*l,
+			"band",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::BinaryXor(l, r) => binary_operation(
+			*l,
+			"bxor",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::BinaryOr(l, r) => binary_operation(
+			*l,
+			"bor",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::Equ(l, r) => binary_operation(
+			*l,
+			"equ",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::NotEqu(l, r) => binary_operation(
+			*l,
+			"nequ",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::LessThan(l, r) => binary_operation(
+			*l,
+			"lt",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::GreaterThan(l, r) => binary_operation(
+			*l,
+			"gt",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::LessThanEqu(l, r) => binary_operation(
+			*l,
+			"lte",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::GreaterThanEqu(l, r) => binary_operation(
+			*l,
+			"gte",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::LogicalAnd(l, r) => binary_operation(
+			*l,
+			"land",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::LogicalOr(l, r) => binary_operation(
+			*l,
+			"lor",
+			*r,
+			env,
+			type_table,
+			symbols,
+			label_index,
+			vtable,
+			str_table,
+			output,
+		),
+		Rpn::Ternary(condition, if_true, if_false) => {
+			// A constant condition only needs the arm it selects.
+			if let Ok(condition) = symbols.eval_local(&condition, vtable) {
+				let arm = if condition != 0 { if_true } else { if_false };
+				return compile_expression(
+					*arm,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					vtable,
+					str_table,
+					output,
+				);
+			}
+
+			let l = *label_index;
+			*label_index += 1;
+
+			compile_condition(
+				*condition,
+				false,
+				&format!(".__else{l}"),
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {result}, LOW({value})",
-				env.expand("put_u8")?
 			)?;
+
+			// Both arms are compiled before being written so that the type of the result is known.
+			let mut true_output = String::new();
+			let true_result = compile_expression(
+				*if_true,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				&mut true_output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			let mut false_output = String::new();
+			let false_result = compile_expression(
+				*if_false,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				&mut false_output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+
+			let result_type = if vtable.type_at(true_result) == vtable.type_at(false_result) {
+				vtable.type_at(true_result).clone()
+			} else {
+				Type::Primative(Primative::from(
+					vtable.type_of(true_result),
+					vtable.type_of(false_result),
+				))
+			};
+			let (true_result, false_result) = match result_type {
+				Type::Primative(t) => (
+					compile_conversion(
+						true_result,
+						t,
+						false,
+						env,
+						label_index,
+						vtable,
+						&mut true_output,
+					)?,
+					compile_conversion(
+						false_result,
+						t,
+						false,
+						env,
+						label_index,
+						vtable,
+						&mut false_output,
+					)?,
+				),
+				_ => (true_result, false_result),
+			};
+			let result = vtable.alloc(result_type.clone())?;
+
+			// Both arms move their value into the same result.
+			write!(output, "{true_output}")?;
+			compile_copy(result, true_result, &result_type, env, output)?;
 			writeln!(
 				output,
-				"\tdb {}, {result} + 1, HIGH({value})",
-				env.expand("put_u8")?
+				"\tdb {}, LOW(.__end{l}), HIGH(.__end{l})",
+				env.expand("jmp")?
 			)?;
-			str_table.push(string);
+			writeln!(output, ".__else{l}")?;
+			write!(output, "{false_output}")?;
+			compile_copy(result, false_result, &result_type, env, output)?;
+			writeln!(output, ".__end{l}")?;
+
+			vtable.autofree(true_result);
+			vtable.autofree(false_result);
+
 			Ok(Some(result))
 		}
-		Rpn::Call(name, args) => match env.lookup(&name)? {
-			types::Definition::Def(def) => {
-				let (def_arg_count, return_id) = validate_args(&def.args, type_table, vtable)?;
+		Rpn::Set(name, i) => {
+			// A plain Set may only assign to existing variables.
+			let (dest, dest_type) = match vtable.lookup_with_type(&name) {
+				Ok(variable) => variable,
+				Err(err) => {
+					let Some((address, t)) = symbols.lookup_extern(&name)? else {
+						return Err(CompilerError::from(err));
+					};
+					let store_type = match t {
+						Type::Primative(t) | Type::Enum(_, t) => t,
+						Type::Pointer(_, t) => t,
+						Type::Struct(..) | Type::Array(..) => {
+							return Err(CompilerError::from(format!("{name} is a {t} and cannot be assigned to. Try working with individual members.")));
+						}
+					};
+					check_enum(&t, &i, env, type_table, vtable, symbols);
 
-				match args.len().cmp(&def_arg_count) {
-					Ordering::Equal => {}
-					Ordering::Greater => return Err(CompilerError::from("Too many arguments")),
-					Ordering::Less => return Err(CompilerError::from("Not enough arguments")),
+					let source = compile_typed_expression(
+						*i,
+						store_type,
+						env,
+						type_table,
+						symbols,
+						label_index,
+						vtable,
+						str_table,
+						output,
+					)?;
+					let pointer = extern_address(&address, t, env, vtable, output)?;
+					compile_store(pointer, source, store_type, env, vtable, output)?;
+					vtable.free(pointer);
+
+					return Ok(Some(source));
 				}
+			};
 
-				let arg_ids = compile_arguments(
-					&def.args, &args, return_id, env, type_table, vtable, str_table, output,
-				)?;
+			if dest_type.is_aggregate() {
+				let dest_type = dest_type.clone();
+				let Rpn::Variable(source_name) = *i else {
+					return Err(CompilerError::from(
+						"Structures may only be assigned from another variable",
+					));
+				};
+				let (source, source_type) = vtable.lookup_with_type(&source_name)?;
 
-				write!(output, "\tdb {}", env.expand(&name)?)?;
-				for i in arg_ids {
-					write!(output, ", {i}")?;
+				if dest_type != *source_type {
+					return Err(CompilerError::from(format!(
+						"Cannot assign a {source_type} to a {dest_type}"
+					)));
 				}
-				writeln!(output)?;
 
-				Ok(return_id)
-			}
-			types::Definition::Alias(def) => {
-				enum AliasVariant {
-					ArgId(usize),
-					ExpressionId(String),
-				}
+				compile_copy(dest, source, &dest_type, env, output)?;
 
-				let (def_arg_count, return_id) = validate_args(&def.args, type_table, vtable)?;
+				return Ok(Some(dest));
+			}
 
-				match args.len().cmp(&def_arg_count) {
-					Ordering::Equal => {}
-					Ordering::Greater => return Err(CompilerError::from("Too many arguments")),
-					Ordering::Less => return Err(CompilerError::from("Not enough arguments")),
-				}
+			check_enum(vtable.type_at(dest), &i, env, type_table, vtable, symbols);
+			let dest_type = vtable.type_of(dest);
+			// TODO: make this directly take ownership of i if it is not an Rpn::Variable.
+			let source = compile_typed_expression(
+				*i,
+				dest_type,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?;
 
-				let arg_ids = compile_arguments(
-					&def.args, &args, return_id, env, type_table, vtable, str_table, output,
-				)?;
+			compile_move(dest, source, dest_type, env, output)?;
 
-				let mut alias_ids = Vec::<AliasVariant>::new();
+			vtable.autofree(source);
 
-				for i in &def.target_args {
-					match i {
-						types::AliasParam::ArgId(index) => {
-							alias_ids.push(AliasVariant::ArgId(*index))
-						}
-						types::AliasParam::Expression(rpn) => {
-							let this_arg = compile_expression(
-								rpn.clone(),
-								env,
-								type_table,
-								vtable,
-								str_table,
-								output,
-							)?
-							.ok_or(String::from("Expression has no return value"))?;
-							alias_ids.push(AliasVariant::ExpressionId(this_arg.to_string()));
-							vtable.autofree(this_arg);
-						}
-						types::AliasParam::Const(rpn) => match rpn {
-							Rpn::Signed(value) => {
-								if *value >= 256 || *value < -128 {
-									return Err(CompilerError::from(
-										"WARN: integer constants can only be 8 bits",
-									));
-								}
-								alias_ids.push(AliasVariant::ExpressionId(value.to_string()));
-							}
-							Rpn::String(text) => {
-								let value = format!(".__string{}", str_table.len());
-								str_table.push(text.clone());
-								alias_ids.push(AliasVariant::ExpressionId(format!(
-									"LOW({value}), HIGH({value})"
-								)));
-							}
-							Rpn::Variable(value) => {
-								alias_ids.push(AliasVariant::ExpressionId(value.to_string()));
-							}
-							_ => return Err(CompilerError::from("Expression must be constant")),
-						},
-					}
+			Ok(Some(dest))
+		}
+		Rpn::SetIndex(name, index, value) => {
+			let (base, element_type, length) = vtable.lookup_array(&name)?;
+			let store_type = match element_type {
+				Type::Primative(t) | Type::Enum(_, t) => t,
+				Type::Pointer(_, t) => t,
+				Type::Struct(..) | Type::Array(..) => {
+					return Err(CompilerError::from(
+						"Cannot assign to structures, assign to individual members instead",
+					))
 				}
+			};
+			check_enum(&element_type, &value, env, type_table, vtable, symbols);
 
-				write!(output, "\tdb {}", env.expand(&name)?)?;
-				for i in alias_ids {
-					match i {
-						AliasVariant::ExpressionId(index) => write!(output, ", {index}")?,
-						AliasVariant::ArgId(index) => {
-							if index > arg_ids.len() {
-								return Err(CompilerError::from(format!(
-									"Argument ID is too large ({index})"
-								)));
-							}
-							write!(output, ", {}", arg_ids[index - 1])?;
-						}
-					}
+			if let Ok(index) = symbols.eval_local(&index, vtable) {
+				if index < 0 || index >= length as i64 {
+					return Err(CompilerError::from(format!(
+						"Index {index} is out of bounds for {name}, which has {length} elements"
+					)));
 				}
-				writeln!(output)?;
-
-				Ok(return_id)
-			}
-			types::Definition::Macro(def) => {
-				let (def_arg_count, return_id) = validate_args(&def.args, type_table, vtable)?;
 
-				match args.len().cmp(&def_arg_count) {
-					Ordering::Equal => {}
-					Ordering::Greater => return Err(CompilerError::from("Too many arguments")),
-					Ordering::Less => return Err(CompilerError::from("Not enough arguments")),
+				let dest = base + index as u8 * store_type.size;
+				if value.is_compound() {
+					vtable.current = Some(dest);
 				}
-
-				let arg_ids = compile_arguments(
-					&def.args, &args, return_id, env, type_table, vtable, str_table, output,
+				let source = compile_typed_expression(
+					*value,
+					store_type,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					vtable,
+					str_table,
+					output,
 				)?;
 
-				write!(output, "\t{}", def.target)?;
-				for i in arg_ids {
-					write!(output, " {i},")?;
-				}
-				writeln!(output)?;
+				compile_move(dest, source, store_type, env, output)?;
 
-				Ok(return_id)
+				vtable.autofree(source);
+
+				return Ok(Some(dest));
 			}
-		},
-		Rpn::Negate(i) => {
-			let operand = compile_expression(*i, env, type_table, vtable, str_table, output)?
-				.ok_or(String::from("Expression has no return value"))?;
-			let operand_type = vtable.type_of(operand);
-			let zero = vtable.alloc(Type::Primative(operand_type))?;
-			let result = vtable.alloc(Type::Primative(operand_type))?;
-			// TODO: make opcodes consider operand size.
-			writeln!(
+
+			let index = compile_expression(
+				*index,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			// The index is only evaluated once, so a compound assignment loads the element through it here.
+			if value.is_compound() {
+				let current = vtable.alloc(element_type)?;
+				writeln!(
+					output,
+					"\tdb {}, {base}, {index}, {current}",
+					env.expand(&store_type.opcode("load_index"))?
+				)?;
+				vtable.current = Some(current);
+			}
+			let source = compile_typed_expression(
+				*value,
+				store_type,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {zero}, $0",
-				env.expand(&format!("put_{operand_type}"))?
 			)?;
+
+			// store_index (array), (index), (value)
 			writeln!(
 				output,
-				"\tdb {}, {zero}, {operand}, {result}",
-				env.expand(&format!("sub_{operand_type}"))?
+				"\tdb {}, {base}, {index}, {source}",
+				env.expand(&store_type.opcode("store_index"))?
 			)?;
 
-			vtable.free(zero);
-			vtable.autofree(operand);
+			vtable.autofree(index);
+
+			Ok(Some(source))
+		}
+		Rpn::Member(pointer, members) => {
+			let pointer = compile_expression(
+				*pointer,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			let (address, member_type) = member_address(pointer, &members, env, vtable, output)?;
+
+			let load_type = match member_type {
+				Type::Primative(t) | Type::Enum(_, t) => t,
+				Type::Pointer(_, t) => t,
+				Type::Struct(..) | Type::Array(..) => {
+					return Err(CompilerError::from(format!("{members} is a structure and cannot be loaded through a pointer. Try working with individual members.")));
+				}
+			};
 
-			Ok(Some(result))
+			let dest = vtable.alloc(member_type)?;
+			compile_load(dest, address, load_type, env, vtable, output)?;
+
+			vtable.autofree(address);
+
+			Ok(Some(dest))
 		}
-		Rpn::Not(i) => {
-			let operand = compile_expression(*i, env, type_table, vtable, str_table, output)?
-				.ok_or(String::from("Expression has no return value"))?;
-			let operand_type = vtable.type_of(operand);
-			// TODO: make the default integer type configurable per-environment
-			let ff = vtable.alloc(Type::Primative(operand_type))?;
-			let result = vtable.alloc(Type::Primative(operand_type))?;
-			writeln!(
+		Rpn::SetMember(pointer, members, value) => {
+			let pointer = compile_expression(
+				*pointer,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {ff}, $FF",
-				env.expand(&format!("put_{operand_type}"))?
-			)?;
-			writeln!(
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+			let (address, member_type) = member_address(pointer, &members, env, vtable, output)?;
+
+			let store_type = match member_type {
+				Type::Primative(t) | Type::Enum(_, t) => t,
+				Type::Pointer(_, t) => t,
+				Type::Struct(..) | Type::Array(..) => {
+					return Err(CompilerError::from(format!("{members} is a structure and cannot be assigned through a pointer. Try working with individual members.")));
+				}
+			};
+			check_enum(&member_type, &value, env, type_table, vtable, symbols);
+			// The pointer is only evaluated once, so a compound assignment loads the member through it here.
+			if value.is_compound() {
+				let current = vtable.alloc(member_type)?;
+				compile_load(current, address, store_type, env, vtable, output)?;
+				vtable.current = Some(current);
+			}
+
+			let source = compile_typed_expression(
+				*value,
+				store_type,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {operand}, {ff}, {result}",
-				env.expand(&format!("xor_{operand_type}"))?
 			)?;
 
-			vtable.free(ff);
-			vtable.autofree(operand);
+			compile_store(address, source, store_type, env, vtable, output)?;
 
-			Ok(Some(result))
+			vtable.autofree(address);
+
+			Ok(Some(source))
 		}
-		Rpn::Deref(i) => {
-			let source = compile_expression(*i, env, type_table, vtable, str_table, output)?
-				.ok_or(String::from("Expression has no return value"))?;
+		Rpn::SetDeref(pointer, value) => {
+			let dest = compile_expression(
+				*pointer,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
 
-			if !vtable.is_pointer(source) {
-				return Err(CompilerError::from("Attempting to deref a non-pointer! Note that address-of returns a `u16`, not a `u16 ptr`. Try declaring the pointer before dereferencing."));
+			if !vtable.is_pointer(dest) {
+				return Err(CompilerError::from(format!(
+					"Cannot store through a {}, expected a pointer",
+					vtable.type_at(dest)
+				)));
 			}
 
-			let source_type = match &vtable.variables[source as usize] {
-				Some(var) => match &var.t {
-					Type::Pointer(t) => match **t {
-						Type::Primative(t) => t,
-						Type::Pointer(..) => Primative::pointer(),
-						Type::Struct(..) => {
-							return Err(CompilerError::from("A pointer to a structure cannot be dereferenced. Try working with individual members."));
-						}
-					},
-					_ => panic!(),
+			let dest_type = match vtable.type_at(dest) {
+				Type::Pointer(t, _) => match **t {
+					Type::Primative(t) | Type::Enum(_, t) => t,
+					Type::Pointer(_, t) => t,
+					Type::Struct(..) | Type::Array(..) => {
+						return Err(CompilerError::from("Cannot assign to a structure through a pointer. Try working with individual members."));
+					}
 				},
-				None => panic!(),
+				_ => panic!(),
 			};
+			if let Type::Pointer(t, _) = vtable.type_at(dest) {
+				check_enum(t, &value, env, type_table, vtable, symbols);
+			}
+			// The pointer is only evaluated once, so a compound assignment loads through it here.
+			if value.is_compound() {
+				let current = vtable.alloc(Type::Primative(dest_type))?;
+				compile_load(current, dest, dest_type, env, vtable, output)?;
+				vtable.current = Some(current);
+			}
 
-			let dest = vtable.alloc(Type::Primative(source_type))?;
-			let dest_type = vtable.type_of(dest);
-
-			writeln!(
+			let source = compile_typed_expression(
+				*value,
+				dest_type,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {dest}, {source}",
-				env.expand(&format!("deref_{dest_type}"))?
 			)?;
 
-			vtable.autofree(source);
+			compile_store(dest, source, dest_type, env, vtable, output)?;
 
-			Ok(Some(dest))
-		}
-		Rpn::Mul(l, r) => {
-			binary_operation(*l, "mul", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::Div(l, r) => {
-			binary_operation(*l, "div", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::Mod(l, r) => {
-			binary_operation(*l, "mod", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::Add(l, r) => {
-			binary_operation(*l, "add", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::Sub(l, r) => {
-			binary_operation(*l, "sub", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::ShiftLeft(l, r) => {
-			binary_operation(*l, "shl", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::ShiftRight(l, r) => {
-			binary_operation(*l, "shr", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::BinaryAnd(l, r) => {
-			binary_operation(*l, "band", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::BinaryXor(l, r) => {
-			binary_operation(*l, "bxor", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::BinaryOr(l, r) => {
-			binary_operation(*l, "bor", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::Equ(l, r) => {
-			binary_operation(*l, "equ", *r, env, type_table, vtable, str_table, output)
-		}
-		Rpn::NotEqu(l, r) => {
-			binary_operation(*l, "nequ", *r, env, type_table, vtable, str_table, output)
+			vtable.autofree(dest);
+
+			Ok(Some(source))
 		}
-		Rpn::LessThan(l, r) => {
-			binary_operation(*l, "lt", *r, env, type_table, vtable, str_table, output)
+	}
+}
+
+/// Finds the type of an expression's value without compiling it, so that enums can be checked before they are lost to their primatives.
+/// Returns `None` if the type is not known until the expression is compiled.
+fn expression_type(
+	rpn: &Rpn,
+	env: &Environment,
+	type_table: &TypeTable,
+	vtable: &VariableTable,
+	symbols: &SymbolTable,
+) -> Option<Type> {
+	match rpn {
+		Rpn::Variable(name) => match vtable.lookup_with_type(name) {
+			Ok((_, t)) => Some(t.clone()),
+			Err(..) => symbols
+				.lookup_constant_type(name)
+				.cloned()
+				.or_else(|| Some(symbols.lookup_extern(name).ok()??.1)),
+		},
+		Rpn::Index(name, _) => vtable.lookup_array(name).ok().map(|(_, t, _)| t),
+		Rpn::Cast(_, t) => type_table.lookup_type(t).ok(),
+		Rpn::Call(name, _) => match env.lookup(name) {
+			Ok(types::Definition::Def(def)) => def.args.iter().find_map(|i| match i {
+				types::DefinitionParam::Return(t) => type_table.lookup_type(t).ok(),
+				_ => None,
+			}),
+			_ => None,
+		},
+		Rpn::Ternary(_, if_true, if_false) => {
+			let t = expression_type(if_true, env, type_table, vtable, symbols)?;
+			(expression_type(if_false, env, type_table, vtable, symbols)? == t).then_some(t)
 		}
-		Rpn::GreaterThan(l, r) => {
-			binary_operation(*l, "gt", *r, env, type_table, vtable, str_table, output)
+		Rpn::String(..)
+		| Rpn::Address(..)
+		| Rpn::Deref(..)
+		| Rpn::Member(..)
+		| Rpn::CallScript(..)
+		| Rpn::Current
+		| Rpn::Set(..)
+		| Rpn::SetIndex(..)
+		| Rpn::SetDeref(..)
+		| Rpn::SetMember(..) => None,
+		// Literals and operators produce plain integers (or bools), even when their operands are enums.
+		_ => Some(Type::Primative(Primative::default_integer())),
+	}
+}
+
+/// Warns when an enum is given a value which is not of that enum, such as a bare integer or a variant of another enum.
+fn check_enum(
+	dest: &Type,
+	rpn: &Rpn,
+	env: &Environment,
+	type_table: &TypeTable,
+	vtable: &VariableTable,
+	symbols: &SymbolTable,
+) {
+	let Type::Enum(dest_name, _) = dest else {
+		return;
+	};
+
+	match expression_type(rpn, env, type_table, vtable, symbols) {
+		Some(Type::Enum(name, _)) if name == *dest_name => {}
+		Some(Type::Enum(name, _)) => {
+			eprintln!("WARN: using a {name} as a {dest_name}. Cast it with `as {dest_name}` if this is intended.");
 		}
-		Rpn::LessThanEqu(l, r) => {
-			binary_operation(*l, "lte", *r, env, type_table, vtable, str_table, output)
+		Some(..) => {
+			eprintln!("WARN: using an integer as a {dest_name}. Cast it with `as {dest_name}` if this is intended.");
 		}
-		Rpn::GreaterThanEqu(l, r) => {
-			binary_operation(*l, "gte", *r, env, type_table, vtable, str_table, output)
+		None => {}
+	}
+}
+
+/// Compiles an Rpn tree whose result must be of type `t`.
+/// Constants become literals of that type, and anything else is converted to it.
+#[allow(clippy::too_many_arguments)]
+fn compile_typed_expression<W: Write>(
+	rpn: Rpn,
+	t: Primative,
+	env: &Environment,
+	type_table: &TypeTable,
+	symbols: &SymbolTable,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	str_table: &mut Vec<String>,
+	output: &mut W,
+) -> Result<u8, CompilerError> {
+	if let Ok(value) = symbols.eval_local(&rpn, vtable) {
+		// `~` only flips the bits that fit in the type, so `u8 x = ~5;` is 250.
+		let value = match rpn {
+			Rpn::Complement(..) if !t.signed => value & t.range().1,
+			_ => value,
+		};
+		return compile_literal(value, t, env, vtable, output);
+	}
+
+	let result = compile_expression(
+		rpn,
+		env,
+		type_table,
+		symbols,
+		label_index,
+		vtable,
+		str_table,
+		output,
+	)?
+	.ok_or(String::from("Expression has no return value"))?;
+	compile_conversion(result, t, false, env, label_index, vtable, output)
+}
+
+/// Compiles a condition which jumps to `target` if it evaluates to `jump_if`.
+/// `&&` and `||` are short-circuited, so their right-hand side is only evaluated if it is needed.
+#[allow(clippy::too_many_arguments)]
+fn compile_condition<W: Write>(
+	condition: Rpn,
+	jump_if: bool,
+	target: &str,
+	env: &Environment,
+	type_table: &TypeTable,
+	symbols: &SymbolTable,
+	label_index: &mut u32,
+	vtable: &mut VariableTable,
+	str_table: &mut Vec<String>,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	match (condition, jump_if) {
+		// If either side of an `&&` is false (or either side of an `||` is true), the jump can be taken immediately.
+		(Rpn::LogicalAnd(l, r), false) | (Rpn::LogicalOr(l, r), true) => {
+			compile_condition(
+				*l,
+				jump_if,
+				target,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?;
+			compile_condition(
+				*r,
+				jump_if,
+				target,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?;
 		}
-		Rpn::LogicalAnd(l, r) => {
-			binary_operation(*l, "land", *r, env, type_table, vtable, str_table, output)
+		// Otherwise, the left side decides whether the right side needs to be checked at all.
+		(Rpn::LogicalAnd(l, r), true) | (Rpn::LogicalOr(l, r), false) => {
+			let l_index = *label_index;
+			*label_index += 1;
+			let skip = format!(".__skip{l_index}");
+
+			compile_condition(
+				*l,
+				!jump_if,
+				&skip,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?;
+			compile_condition(
+				*r,
+				jump_if,
+				target,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?;
+
+			writeln!(output, "{skip}")?;
 		}
-		Rpn::LogicalOr(l, r) => {
-			binary_operation(*l, "lor", *r, env, type_table, vtable, str_table, output)
+		// `!` on a condition only needs to flip the jump.
+		(Rpn::Not(i), jump_if) if i.is_boolean() => {
+			compile_condition(
+				*i,
+				!jump_if,
+				target,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?;
 		}
-		Rpn::Set(name, i) => {
-			// A plain Set may only assign to existing variables.
-			let dest = vtable.lookup(&name)?;
-			let dest_type = vtable.type_of(dest);
-			// TODO: make this directly take ownership of i if it is not an Rpn::Variable.
-			let source = compile_expression(*i, env, type_table, vtable, str_table, output)?
-				.ok_or(String::from("Expression has no return value"))?;
+		(condition, jump_if) => {
+			let result = compile_expression(
+				condition,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(String::from("Expression has no return value"))?;
+
+			let result_type = vtable.type_of(result);
+			if !result_type.boolean {
+				eprintln!("WARN: using a {result_type} as a condition. Compare it against 0 if this is intended.");
+			}
 
 			writeln!(
 				output,
-				"\tdb {}, {dest}, {source}",
-				env.expand(&format!("mov_{dest_type}"))?
+				"\tdb {}, {result}, LOW({target}), HIGH({target})",
+				env.expand(if jump_if {
+					"jmp_if_true"
+				} else {
+					"jmp_if_false"
+				})?
 			)?;
 
-			vtable.autofree(source);
-
-			Ok(Some(dest))
+			vtable.autofree(result);
 		}
 	}
+
+	Ok(())
 }
 
+/// Compiles a statement, giving any error without a location the statement's location.
+#[allow(clippy::too_many_arguments)]
 fn compile_statement<W: Write>(
 	statement: Statement,
 	env: &Environment,
 	type_table: &TypeTable,
+	symbols: &SymbolTable,
+	label_index: &mut u32,
+	loop_stack: &mut Vec<LoopLabels>,
+	vtable: &mut VariableTable,
+	str_table: &mut Vec<String>,
+	output: &mut W,
+) -> Result<(), CompilerError> {
+	let (start, end) = (statement.start, statement.end);
+
+	compile_statement_contents(
+		statement,
+		env,
+		type_table,
+		symbols,
+		label_index,
+		loop_stack,
+		vtable,
+		str_table,
+		output,
+	)
+	.map_err(|error| match error.start {
+		Some(..) => error,
+		None => CompilerError {
+			start: Some(start),
+			end: Some(end),
+			..error
+		},
+	})
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compile_statement_contents<W: Write>(
+	statement: Statement,
+	env: &Environment,
+	type_table: &TypeTable,
+	symbols: &SymbolTable,
 	label_index: &mut u32,
+	loop_stack: &mut Vec<LoopLabels>,
 	vtable: &mut VariableTable,
 	str_table: &mut Vec<String>,
 	output: &mut W,
@@ -1121,13 +3999,26 @@ fn compile_statement<W: Write>(
 
 	match statement.t {
 		StatementType::Expression(rpn) => {
-			if let Err(msg) = compile_expression(rpn, env, type_table, vtable, str_table, output) {
-				// TODO: Give Rpn nodes their own location info.
-				return Err(CompilerError {
-					start: Some(statement.start),
-					end: Some(statement.end),
-					msg: msg.to_string(),
-				});
+			match compile_expression(
+				rpn,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			) {
+				Ok(Some(result)) => vtable.autofree(result),
+				Ok(None) => {}
+				Err(msg) => {
+					// TODO: Give Rpn nodes their own location info.
+					return Err(CompilerError {
+						start: Some(statement.start),
+						end: Some(statement.end),
+						msg: msg.to_string(),
+					});
+				}
 			}
 		}
 		StatementType::Declaration(t, name) => {
@@ -1136,83 +4027,146 @@ fn compile_statement<W: Write>(
 		}
 		StatementType::PointerDeclaration(t, name) => {
 			let object_type = type_table.lookup_type(&t)?;
-			let new_var = vtable.alloc(Type::Pointer(Box::new(object_type)))?;
+			let new_var = vtable.alloc(Type::Pointer(Box::new(object_type), env.pointer))?;
+			*vtable.name_of(new_var) = Some(name);
+		}
+		StatementType::ArrayDeclaration(t, name, length) => {
+			let length = symbols
+				.eval_local(&length, vtable)
+				.map_err(statement_error)?;
+			if !(1..=255).contains(&length) {
+				return Err(statement_error(String::from(
+					"Array length must be between 1 and 255",
+				)));
+			}
+
+			let element_type = type_table.lookup_type(&t)?;
+			if element_type.size() as i64 * length > 255 {
+				return Err(statement_error(format!(
+					"{name} is too large; a single function is limited to 256 bytes"
+				)));
+			}
+
+			let new_var = vtable.alloc(Type::Array(Box::new(element_type), length as u8))?;
 			*vtable.name_of(new_var) = Some(name);
 		}
 		StatementType::DeclareAssign(t, name, rpn) => {
+			let dest_type = type_table.lookup_type(&t)?;
+
 			match rpn {
-				Rpn::Variable(source_name) => {
+				// Structures are copied directly from another variable.
+				Rpn::Variable(source_name)
+					if dest_type.is_aggregate()
+						|| vtable
+							.lookup_with_type(&source_name)
+							.is_ok_and(|(_, t)| t.is_aggregate()) =>
+				{
 					// Create a new variable
-					let dest_type =
-						match type_table.lookup_primative(&t) {
-							Ok(t) => t,
-							Err(..) => return Err(statement_error(String::from(
-								"Cannot assign to structures, assign to individual members instead",
-							))),
-						};
-					let dest = vtable.alloc(Type::Primative(dest_type))?;
-					let source = vtable.lookup(&source_name)?;
+					let (source, source_type) = vtable.lookup_with_type(&source_name)?;
+
+					if (dest_type.is_aggregate() || source_type.is_aggregate())
+						&& dest_type != *source_type
+					{
+						return Err(statement_error(format!(
+							"Cannot assign a {source_type} to a {dest_type}"
+						)));
+					}
 
-					writeln!(
-						output,
-						"\tdb {}, {dest}, {source}",
-						env.expand(&format!("mov_{dest_type}"))?
-					)?;
+					let dest = vtable.alloc(dest_type.clone())?;
+					compile_copy(dest, source, &dest_type, env, output)?;
 
 					*vtable.name_of(dest) = Some(name);
 
 					vtable.autofree(source);
 				}
 				_ => {
-					let new_var =
-						compile_expression(rpn, env, type_table, vtable, str_table, output)?
-							.ok_or(statement_error(String::from(
-								"Expression has no return value",
-							)))?;
+					check_enum(&dest_type, &rpn, env, type_table, vtable, symbols);
+					let dest_primative = match dest_type {
+						Type::Primative(t) | Type::Enum(_, t) => t,
+						Type::Pointer(_, t) => t,
+						Type::Struct(..) | Type::Array(..) => {
+							return Err(statement_error(String::from(
+								"Structures may only be assigned from another variable",
+							)));
+						}
+					};
+
+					let mut new_var = compile_typed_expression(
+						rpn,
+						dest_primative,
+						env,
+						type_table,
+						symbols,
+						label_index,
+						vtable,
+						str_table,
+						output,
+					)?;
+
+					// Expressions like constant array indices refer to existing variables, which must be copied.
+					if !vtable.is_temporary(new_var) {
+						let dest_type = vtable.type_of(new_var);
+						let dest = vtable.alloc(Type::Primative(dest_type))?;
+						compile_move(dest, new_var, dest_type, env, output)?;
+						new_var = dest;
+					}
+
+					*vtable.type_of_mut(new_var) = dest_type;
 					*vtable.name_of(new_var) = Some(name);
 				}
 			}
 		}
 		StatementType::PointerDeclareAssign(t, name, rpn) => {
-			let dest_type = match type_table.lookup_primative(&t) {
-				Ok(t) => t,
-				Err(..) => {
-					return Err(statement_error(String::from(
-						"Cannot assign to structures, assign to individual members instead",
-					)))
-				}
-			};
-			let dest = vtable.alloc(Type::Pointer(Box::new(Type::Primative(dest_type))))?;
+			let dest_type = type_table.lookup_type(&t)?;
+			let dest = vtable.alloc(Type::Pointer(Box::new(dest_type), env.pointer))?;
 			*vtable.name_of(dest) = Some(name);
 
-			let source = compile_expression(rpn, env, type_table, vtable, str_table, output)?
-				.ok_or(statement_error(String::from(
-					"Expression has no return value",
-				)))?;
+			let source = compile_typed_expression(
+				rpn,
+				env.pointer,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?;
 
-			writeln!(output, "\tdb {}, {dest}, {source}", env.expand("mov_u16")?)?;
+			compile_move(dest, source, env.pointer, env, output)?;
 
 			vtable.autofree(source);
 		}
 		StatementType::If(condition, contents, else_contents) => {
-			let condition_result =
-				compile_expression(condition, env, type_table, vtable, str_table, output)?.ok_or(
-					statement_error(String::from("Expression has no return value")),
-				)?;
 			let l = *label_index;
 			*label_index += 1;
 
-			writeln!(
+			compile_condition(
+				condition,
+				false,
+				&format!(".__else{l}"),
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {condition_result}, LOW(.__else{l}), HIGH(.__else{l})",
-				env.expand("jmp_if_false")?
 			)?;
 
-			vtable.autofree(condition_result);
-
 			vtable.push_scope();
 			for i in contents {
-				compile_statement(i, env, type_table, label_index, vtable, str_table, output)?;
+				compile_statement(
+					i,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					loop_stack,
+					vtable,
+					str_table,
+					output,
+				)?;
 			}
 			vtable.pop_scope();
 
@@ -1229,14 +4183,24 @@ fn compile_statement<W: Write>(
 			if let Some(else_statements) = else_contents {
 				vtable.push_scope();
 				for i in else_statements {
-					compile_statement(i, env, type_table, label_index, vtable, str_table, output)?;
+					compile_statement(
+						i,
+						env,
+						type_table,
+						symbols,
+						label_index,
+						loop_stack,
+						vtable,
+						str_table,
+						output,
+					)?;
 				}
 				vtable.pop_scope();
 			}
 
 			writeln!(output, ".__end{l}")?;
 		}
-		StatementType::While(condition, contents) => {
+		StatementType::While(label, condition, contents) => {
 			let l = *label_index;
 			*label_index += 1;
 
@@ -1249,55 +4213,93 @@ fn compile_statement<W: Write>(
 
 			writeln!(output, ".__while{l}")?;
 
+			loop_stack.push(LoopLabels {
+				name: label,
+				continue_label: format!(".__end{l}"),
+				break_label: format!(".__break{l}"),
+			});
+
 			vtable.push_scope();
 			for i in contents {
-				compile_statement(i, env, type_table, label_index, vtable, str_table, output)?;
+				compile_statement(
+					i,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					loop_stack,
+					vtable,
+					str_table,
+					output,
+				)?;
 			}
 			vtable.pop_scope();
+			loop_stack.pop();
 
 			writeln!(output, ".__end{l}")?;
 
-			let condition_result =
-				compile_expression(condition, env, type_table, vtable, str_table, output)?.ok_or(
-					statement_error(String::from("Expression has no return value")),
-				)?;
-
-			writeln!(
+			compile_condition(
+				condition,
+				true,
+				&format!(".__while{l}"),
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {condition_result}, LOW(.__while{l}), HIGH(.__while{l})",
-				env.expand("jmp_if_true")?
 			)?;
 
-			vtable.autofree(condition_result);
+			writeln!(output, ".__break{l}")?;
 		}
-		StatementType::Do(condition, contents) => {
+		StatementType::Do(label, condition, contents) => {
 			let l = *label_index;
 			*label_index += 1;
 
 			writeln!(output, ".__while{l}")?;
 
+			loop_stack.push(LoopLabels {
+				name: label,
+				continue_label: format!(".__end{l}"),
+				break_label: format!(".__break{l}"),
+			});
+
 			vtable.push_scope();
 			for i in contents {
-				compile_statement(i, env, type_table, label_index, vtable, str_table, output)?;
+				compile_statement(
+					i,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					loop_stack,
+					vtable,
+					str_table,
+					output,
+				)?;
 			}
 			vtable.pop_scope();
+			loop_stack.pop();
 
 			writeln!(output, ".__end{l}")?;
 
-			let condition_result =
-				compile_expression(condition, env, type_table, vtable, str_table, output)?.ok_or(
-					statement_error(String::from("Expression has no return value")),
-				)?;
-
-			writeln!(
+			compile_condition(
+				condition,
+				true,
+				&format!(".__while{l}"),
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {condition_result}, LOW(.__while{l}), HIGH(.__while{l})",
-				env.expand("jmp_if_true")?
 			)?;
 
-			vtable.autofree(condition_result);
+			writeln!(output, ".__break{l}")?;
 		}
-		StatementType::For(prologue, condition, epilogue, contents) => {
+		StatementType::For(label, prologue, condition, epilogue, contents) => {
 			let l = *label_index;
 			*label_index += 1;
 
@@ -1306,7 +4308,9 @@ fn compile_statement<W: Write>(
 				*prologue,
 				env,
 				type_table,
+				symbols,
 				label_index,
+				loop_stack,
 				vtable,
 				str_table,
 				output,
@@ -1321,18 +4325,39 @@ fn compile_statement<W: Write>(
 
 			writeln!(output, ".__for{l}")?;
 
+			loop_stack.push(LoopLabels {
+				name: label,
+				continue_label: format!(".__continue{l}"),
+				break_label: format!(".__break{l}"),
+			});
+
 			vtable.push_scope();
 			for i in contents {
-				compile_statement(i, env, type_table, label_index, vtable, str_table, output)?;
+				compile_statement(
+					i,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					loop_stack,
+					vtable,
+					str_table,
+					output,
+				)?;
 			}
 			vtable.pop_scope();
+			loop_stack.pop();
+
+			writeln!(output, ".__continue{l}")?;
 
 			// Execute epliogue before checking condition
 			compile_statement(
 				*epilogue,
 				env,
 				type_table,
+				symbols,
 				label_index,
+				loop_stack,
 				vtable,
 				str_table,
 				output,
@@ -1340,93 +4365,283 @@ fn compile_statement<W: Write>(
 
 			writeln!(output, ".__end{l}")?;
 
-			let condition_result =
-				compile_expression(condition, env, type_table, vtable, str_table, output)?.ok_or(
-					statement_error(String::from("Expression has no return value")),
-				)?;
-
-			writeln!(
+			compile_condition(
+				condition,
+				true,
+				&format!(".__for{l}"),
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
 				output,
-				"\tdb {}, {condition_result}, LOW(.__for{l}), HIGH(.__for{l})",
-				env.expand("jmp_if_true")?
 			)?;
 
-			vtable.autofree(condition_result);
+			writeln!(output, ".__break{l}")?;
 		}
-		StatementType::Repeat(repeat_count, contents) => {
+		StatementType::Repeat(label, index_name, repeat_count, contents) => {
 			let l = *label_index;
 			*label_index += 1;
 
 			// Execute prologue
-			let mut repeat_index =
-				compile_expression(repeat_count, env, type_table, vtable, str_table, output)?
-					.ok_or(statement_error(String::from(
-						"Expression has no return value",
-					)))?;
+			let mut repeat_index = compile_expression(
+				repeat_count,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(statement_error(String::from(
+				"Expression has no return value",
+			)))?;
 
-			if vtable.name_of(repeat_index).is_some() {
+			if !vtable.is_temporary(repeat_index) {
 				let dest_type = vtable.type_of(repeat_index);
 				let unique_index = vtable.alloc(Type::Primative(dest_type))?;
-				writeln!(
-					output,
-					"\tdb {}, {unique_index}, {repeat_index}",
-					env.expand(&format!("mov_{dest_type}"))?
-				)?;
+				compile_move(unique_index, repeat_index, dest_type, env, output)?;
 				repeat_index = unique_index;
 			}
 
-			writeln!(output, ".__repeat{l}")?;
+			if let Some(index_name) = index_name {
+				if vtable.lookup(&index_name).is_ok() {
+					return Err(statement_error(format!(
+						"Variable {index_name} already exists"
+					)));
+				}
 
-			vtable.push_scope();
-			for i in contents {
-				compile_statement(i, env, type_table, label_index, vtable, str_table, output)?;
-			}
-			vtable.pop_scope();
+				// The index is scoped to the loop, and counts up until it reaches the repeat count.
+				let index_type = vtable.type_of(repeat_index);
+				vtable.push_scope();
+				let index = vtable.alloc(Type::Primative(index_type))?;
+				*vtable.name_of(index) = Some(index_name);
 
-			// Execute epilogue before checking condition
-			let scratch = vtable.alloc(Type::Primative(Primative {
-				signed: false,
-				size: 1,
-			}))?;
+				compile_put(index, "0", index_type, env, output)?;
 
-			writeln!(output, "\tdb {}, {scratch}, $1", env.expand("put_u8")?)?;
+				writeln!(output, ".__repeat{l}")?;
 
-			writeln!(
-				output,
-				"\tdb {}, {repeat_index}, {scratch}, {repeat_index}",
-				env.expand("sub_u8")?
-			)?;
+				loop_stack.push(LoopLabels {
+					name: label,
+					continue_label: format!(".__continue{l}"),
+					break_label: format!(".__break{l}"),
+				});
 
-			writeln!(output, ".__end{l}")?;
+				vtable.push_scope();
+				for i in contents {
+					compile_statement(
+						i,
+						env,
+						type_table,
+						symbols,
+						label_index,
+						loop_stack,
+						vtable,
+						str_table,
+						output,
+					)?;
+				}
+				vtable.pop_scope();
+				loop_stack.pop();
 
-			writeln!(output, "\tdb {}, {scratch}, $0", env.expand("put_u8")?)?;
+				writeln!(output, ".__continue{l}")?;
 
-			writeln!(
-				output,
-				"\tdb {}, {repeat_index}, {scratch}, {scratch}",
-				env.expand("equ_u8")?
-			)?;
+				if env.lookup(&index_type.opcode("inc")).is_ok() {
+					writeln!(
+						output,
+						"\tdb {}, {index}",
+						env.expand(&index_type.opcode("inc"))?
+					)?;
+				} else {
+					let one = compile_literal(1, index_type, env, vtable, output)?;
+					compile_operation("add", index, one, index, index_type, env, vtable, output)?;
+					vtable.free(one);
+				}
 
-			writeln!(
-				output,
-				"\tdb {}, {scratch}, LOW(.__repeat{l}), HIGH(.__repeat{l})",
-				env.expand("jmp_if_false")?
-			)?;
+				writeln!(output, ".__end{l}")?;
+
+				let scratch = vtable.alloc(Type::Primative(Primative::boolean()))?;
+				compile_operation(
+					"equ",
+					index,
+					repeat_index,
+					scratch,
+					index_type,
+					env,
+					vtable,
+					output,
+				)?;
+
+				writeln!(
+					output,
+					"\tdb {}, {scratch}, LOW(.__repeat{l}), HIGH(.__repeat{l})",
+					env.expand("jmp_if_false")?
+				)?;
+
+				vtable.free(scratch);
+				vtable.pop_scope();
+
+				writeln!(output, ".__break{l}")?;
+			} else {
+				writeln!(output, ".__repeat{l}")?;
+
+				loop_stack.push(LoopLabels {
+					name: label,
+					continue_label: format!(".__continue{l}"),
+					break_label: format!(".__break{l}"),
+				});
+
+				vtable.push_scope();
+				for i in contents {
+					compile_statement(
+						i,
+						env,
+						type_table,
+						symbols,
+						label_index,
+						loop_stack,
+						vtable,
+						str_table,
+						output,
+					)?;
+				}
+				vtable.pop_scope();
+				loop_stack.pop();
+
+				writeln!(output, ".__continue{l}")?;
+
+				// Execute epilogue before checking condition
+				let count_type = vtable.type_of(repeat_index);
+				let operand = compile_literal(1, count_type, env, vtable, output)?;
+				let scratch = vtable.alloc(Type::Primative(Primative::boolean()))?;
+
+				compile_operation(
+					"sub",
+					repeat_index,
+					operand,
+					repeat_index,
+					count_type,
+					env,
+					vtable,
+					output,
+				)?;
+
+				writeln!(output, ".__end{l}")?;
+
+				compile_put(operand, "0", count_type, env, output)?;
+				compile_operation(
+					"equ",
+					repeat_index,
+					operand,
+					scratch,
+					count_type,
+					env,
+					vtable,
+					output,
+				)?;
+				vtable.free(operand);
+
+				writeln!(
+					output,
+					"\tdb {}, {scratch}, LOW(.__repeat{l}), HIGH(.__repeat{l})",
+					env.expand("jmp_if_false")?
+				)?;
+
+				vtable.autofree(scratch);
+
+				writeln!(output, ".__break{l}")?;
+			}
 
-			vtable.autofree(scratch);
 			vtable.autofree(repeat_index);
 		}
-		StatementType::Loop(contents) => {
+		StatementType::Return(rpn) => {
+			// The first return decides the script's return type.
+			let result = match vtable.return_type {
+				Some(return_type) => compile_typed_expression(
+					rpn,
+					return_type,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					vtable,
+					str_table,
+					output,
+				)?,
+				None => compile_expression(
+					rpn,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					vtable,
+					str_table,
+					output,
+				)?
+				.ok_or(statement_error(String::from(
+					"Expression has no return value",
+				)))?,
+			};
+			let result_type = vtable.type_of(result);
+			let return_type = *vtable.return_type.get_or_insert(result_type);
+
+			if result_type != return_type {
+				eprintln!(
+					"WARN: returning a {result_type}, but a previous return used {return_type}"
+				);
+			}
+			let result =
+				compile_conversion(result, return_type, false, env, label_index, vtable, output)?;
+
+			// The return value is not given a slot of its own, since its type isn't known until the first `return`.
+			// Instead it overwrites the start of the parameter area, which is safe because nothing runs after `ret`.
+			// `peak_usage` only needs to cover it, in case it is wider than what was allocated there.
+			let return_end =
+				vtable
+					.shared_size
+					.checked_add(return_type.size)
+					.ok_or(statement_error(String::from(
+						"The return value does not fit in the pool",
+					)))?;
+			if vtable.peak_usage < return_end {
+				vtable.peak_usage = return_end;
+			}
+
+			compile_move(vtable.shared_size, result, return_type, env, output)?;
+			writeln!(output, "\tdb {}", env.expand("ret")?)?;
+
+			vtable.autofree(result);
+		}
+		StatementType::Loop(label, contents) => {
 			let l = *label_index;
 			*label_index += 1;
 
 			writeln!(output, ".__loop{l}")?;
 
+			loop_stack.push(LoopLabels {
+				name: label,
+				continue_label: format!(".__loop{l}"),
+				break_label: format!(".__end{l}"),
+			});
+
 			vtable.push_scope();
 			for i in contents {
-				compile_statement(i, env, type_table, label_index, vtable, str_table, output)?;
+				compile_statement(
+					i,
+					env,
+					type_table,
+					symbols,
+					label_index,
+					loop_stack,
+					vtable,
+					str_table,
+					output,
+				)?;
 			}
 			vtable.pop_scope();
+			loop_stack.pop();
 
 			writeln!(
 				output,
@@ -1436,6 +4651,207 @@ fn compile_statement<W: Write>(
 
 			writeln!(output, ".__end{l}")?;
 		}
+		StatementType::Switch(value, cases, default) => {
+			let l = *label_index;
+			*label_index += 1;
+
+			// The value is only evaluated once, and then compared against each case.
+			let value = compile_expression(
+				value,
+				env,
+				type_table,
+				symbols,
+				label_index,
+				vtable,
+				str_table,
+				output,
+			)?
+			.ok_or(statement_error(String::from(
+				"Expression has no return value",
+			)))?;
+			let value_type = vtable.type_of(value);
+
+			let fallback = if default.is_some() {
+				format!(".__default{l}")
+			} else {
+				format!(".__end{l}")
+			};
+
+			let mut constants = Vec::<(i64, usize)>::new();
+			for (case_index, case) in cases.iter().enumerate() {
+				for i in &case.values {
+					if let Ok(constant) = symbols.eval_local(i, vtable) {
+						if constants.iter().any(|(other, _)| *other == constant) {
+							return Err(statement_error(format!(
+								"Case {constant} is handled more than once"
+							)));
+						}
+						constants.push((constant, case_index));
+					}
+				}
+			}
+
+			let case_count = cases.iter().map(|case| case.values.len()).sum::<usize>();
+			let min = constants.iter().map(|(i, _)| *i).min().unwrap_or(0);
+			let max = constants.iter().map(|(i, _)| *i).max().unwrap_or(0);
+			let (lower_bound, upper_bound) = if value_type.signed {
+				(-128, 127)
+			} else {
+				(0, 255)
+			};
+
+			// A jump table is only worthwhile if most of its entries are actual cases.
+			let use_jump_table = env.lookup("jmp_table").is_ok()
+				&& value_type.size == 1
+				&& constants.len() == case_count
+				&& case_count >= 3
+				&& min >= lower_bound
+				&& max <= upper_bound
+				&& max - min < case_count as i64 * 2;
+
+			if use_jump_table {
+				// jmp_table (index), (entry count), (entries)...
+				// Indices outside of the table continue to the next instruction.
+				let index = if min == 0 {
+					value
+				} else {
+					let min_id = compile_literal(min, value_type, env, vtable, output)?;
+					let index = vtable.alloc(Type::Primative(value_type))?;
+					compile_operation(
+						"sub", value, min_id, index, value_type, env, vtable, output,
+					)?;
+					vtable.free(min_id);
+					index
+				};
+
+				writeln!(
+					output,
+					"\tdb {}, {index}, {}",
+					env.expand("jmp_table")?,
+					max - min + 1
+				)?;
+
+				for i in min..=max {
+					let target = match constants.iter().find(|(constant, _)| *constant == i) {
+						Some((_, case_index)) => format!(".__case{l}_{case_index}"),
+						None => fallback.clone(),
+					};
+					writeln!(output, "\tdb LOW({target}), HIGH({target})")?;
+				}
+
+				if index != value {
+					vtable.free(index);
+				}
+			} else {
+				for (case_index, case) in cases.iter().enumerate() {
+					for i in &case.values {
+						let case_value = compile_typed_expression(
+							i.clone(),
+							value_type,
+							env,
+							type_table,
+							symbols,
+							label_index,
+							vtable,
+							str_table,
+							output,
+						)?;
+						let scratch = vtable.alloc(Type::Primative(Primative::boolean()))?;
+						compile_operation(
+							"equ", value, case_value, scratch, value_type, env, vtable, output,
+						)?;
+						writeln!(
+							output,
+							"\tdb {}, {scratch}, LOW(.__case{l}_{case_index}), HIGH(.__case{l}_{case_index})",
+							env.expand("jmp_if_true")?
+						)?;
+
+						vtable.free(scratch);
+						vtable.autofree(case_value);
+					}
+				}
+			}
+
+			vtable.autofree(value);
+
+			writeln!(
+				output,
+				"\tdb {}, LOW({fallback}), HIGH({fallback})",
+				env.expand("jmp")?
+			)?;
+
+			let case_count = cases.len();
+			for (case_index, case) in cases.into_iter().enumerate() {
+				writeln!(output, ".__case{l}_{case_index}")?;
+
+				vtable.push_scope();
+				for i in case.contents {
+					compile_statement(
+						i,
+						env,
+						type_table,
+						symbols,
+						label_index,
+						loop_stack,
+						vtable,
+						str_table,
+						output,
+					)?;
+				}
+				vtable.pop_scope();
+
+				// Cases do not fall through.
+				if case_index + 1 != case_count || default.is_some() {
+					writeln!(
+						output,
+						"\tdb {}, LOW(.__end{l}), HIGH(.__end{l})",
+						env.expand("jmp")?
+					)?;
+				}
+			}
+
+			if let Some(default) = default {
+				writeln!(output, ".__default{l}")?;
+
+				vtable.push_scope();
+				for i in default {
+					compile_statement(
+						i,
+						env,
+						type_table,
+						symbols,
+						label_index,
+						loop_stack,
+						vtable,
+						str_table,
+						output,
+					)?;
+				}
+				vtable.pop_scope();
+			}
+
+			writeln!(output, ".__end{l}")?;
+		}
+		StatementType::Break(target) => {
+			let target = lookup_loop(loop_stack, "break", target).map_err(statement_error)?;
+
+			writeln!(
+				output,
+				"\tdb {}, LOW({1}), HIGH({1})",
+				env.expand("jmp")?,
+				target.break_label
+			)?;
+		}
+		StatementType::Continue(target) => {
+			let target = lookup_loop(loop_stack, "continue", target).map_err(statement_error)?;
+
+			writeln!(
+				output,
+				"\tdb {}, LOW({1}), HIGH({1})",
+				env.expand("jmp")?,
+				target.continue_label
+			)?;
+		}
 		_ => {
 			return Err(CompilerError {
 				start: Some(statement.start),
@@ -1453,9 +4869,10 @@ fn compile_function<W: Write>(
 	func: types::Function,
 	environment_table: &EnvironmentTable,
 	type_table: &TypeTable,
+	symbols: &SymbolTable,
 	output: &mut W,
 	options: &CompilerOptions,
-) -> Result<(), CompilerError> {
+) -> Result<Script, CompilerError> {
 	let env = match environment_table.get(&func.environment) {
 		Some(env) => env,
 		None => {
@@ -1466,9 +4883,42 @@ fn compile_function<W: Write>(
 			})
 		}
 	};
-	let mut vtable = VariableTable::new();
+	// Constants from the script's environment are visible alongside global ones.
+	let symbols = &symbols.merge(&env.symbols);
+	let mut vtable = VariableTable::new(env);
 	let mut str_table = Vec::<String>::new();
 	let mut label_index = 0;
+	let mut loop_stack = Vec::<LoopLabels>::new();
+	let mut params = Vec::<(u8, Type)>::new();
+
+	// Parameters are allocated before anything but shared variables, so that they always begin at the same offset.
+	for param in func.args {
+		let function_error = |msg: String| -> CompilerError {
+			CompilerError {
+				start: Some(func.start),
+				end: Some(func.end),
+				msg,
+			}
+		};
+
+		if vtable.lookup(&param.name).is_ok() {
+			return Err(function_error(format!(
+				"Parameter {} is declared more than once",
+				param.name
+			)));
+		}
+
+		let param_type = type_table.lookup_type(&param.t).map_err(function_error)?;
+		let param_type = if param.is_pointer {
+			Type::Pointer(Box::new(param_type), env.pointer)
+		} else {
+			param_type
+		};
+		let param_id = vtable.alloc(param_type.clone()).map_err(function_error)?;
+		params.push((param_id, param_type));
+		writeln!(output, "def {name}@{} equ {param_id}", param.name)?;
+		*vtable.name_of(param_id) = Some(param.name);
+	}
 
 	writeln!(output, "\nsection \"{name} evscript fn\", romx\n{name}::")?;
 
@@ -1477,7 +4927,9 @@ fn compile_function<W: Write>(
 			i,
 			env,
 			type_table,
+			symbols,
 			&mut label_index,
+			&mut loop_stack,
 			&mut vtable,
 			&mut str_table,
 			output,
@@ -1486,9 +4938,20 @@ fn compile_function<W: Write>(
 
 	writeln!(output, "\tdb 0")?;
 
+	// This shares its offset with the first parameter; see `StatementType::Return`.
+	if let Some(return_type) = vtable.return_type {
+		writeln!(output, "def {name}@return equ {}", vtable.shared_size)?;
+		writeln!(output, "def {name}@return_size equ {}", return_type.size)?;
+		writeln!(output, "def {name}@return_type equs \"{return_type}\"")?;
+	}
+
 	let mut i = 0;
 	while i < str_table.len() {
-		writeln!(output, ".__string{i} db \"{}\", 0", str_table[i])?;
+		writeln!(
+			output,
+			".__string{i} db {}",
+			string_bytes(&str_table[i], symbols)
+		)?;
 		i += 1;
 	}
 
@@ -1503,6 +4966,65 @@ fn compile_function<W: Write>(
 		);
 	}
 
+	Ok(Script {
+		environment: env.name.clone(),
+		bytecodes: env
+			.definitions
+			.iter()
+			.filter_map(|(name, def)| match def {
+				types::Definition::Def(def) => Some((name.clone(), def.bytecode)),
+				_ => None,
+			})
+			.collect(),
+		variables: env.variables.clone(),
+		params,
+		return_type: vtable.return_type,
+		return_offset: vtable.shared_size,
+		peak_usage: vtable.peak_usage,
+	})
+}
+
+/// Reads the `charmap` lines of an RGBDS include file, ignoring everything else.
+fn include_charmap(path: &str, symbols: &mut SymbolTable) -> Result<(), CompilerError> {
+	let input = read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+	let mut source = String::new();
+
+	for line in input.lines() {
+		// Strip comments, taking care not to stop at a `;` inside of a string.
+		let mut in_string = false;
+		let mut escaped = false;
+		let mut end = line.len();
+		for (i, c) in line.char_indices() {
+			match c {
+				_ if escaped => escaped = false,
+				'\\' => escaped = true,
+				'"' => in_string = !in_string,
+				';' if !in_string => {
+					end = i;
+					break;
+				}
+				_ => {}
+			}
+		}
+		let line = line[..end].trim();
+
+		// RGBDS directives are case-insensitive, but evscript keywords are not.
+		if let (Some(directive), Some(entry)) = (line.get(..7), line.get(7..)) {
+			if directive.eq_ignore_ascii_case("charmap") && entry.starts_with(char::is_whitespace) {
+				source.push_str(&format!("charmap {entry};\n"));
+			}
+		}
+	}
+
+	let ast = crate::parse(&source).map_err(|err| format!("{path}: {err}"))?;
+	for i in ast {
+		if let types::Root::Charmap { text, values } = i {
+			symbols
+				.define_charmap(text, &values)
+				.map_err(|err| format!("{path}: {err}"))?;
+		}
+	}
+
 	Ok(())
 }
 
@@ -1510,18 +5032,35 @@ fn compile_ast<W: Write>(
 	ast: Vec<types::Root>,
 	environment_table: &mut EnvironmentTable,
 	type_table: &mut TypeTable,
+	symbols: &mut SymbolTable,
 	output: &mut W,
 	options: &CompilerOptions,
 ) -> Result<(), CompilerError> {
 	for i in ast {
 		match i {
 			types::Root::Environment(name, env) => {
-				let new_env = compile_environment(&name, env, environment_table, output)?;
+				let new_env = compile_environment(
+					&name,
+					env,
+					environment_table,
+					type_table,
+					symbols,
+					output,
+				)?;
 				writeln!(output, "def {name}__pool_size equ {}", new_env.pool)?;
 				environment_table.insert(name, new_env);
 			}
 			types::Root::Function(name, func) => {
-				compile_function(&name, func, environment_table, type_table, output, options)?;
+				let script = compile_function(
+					&name,
+					func,
+					environment_table,
+					type_table,
+					symbols,
+					output,
+					options,
+				)?;
+				symbols.define_symbol(&name, Symbol::Script(script))?;
 			}
 			types::Root::Assembly(contents) => {
 				writeln!(output, "{}", contents)?;
@@ -1543,7 +5082,9 @@ fn compile_ast<W: Write>(
 					}
 				};
 
-				if let Err(err) = compile_ast(ast, environment_table, type_table, output, options) {
+				if let Err(err) =
+					compile_ast(ast, environment_table, type_table, symbols, output, options)
+				{
 					eprintln!("{path}: {err}");
 					exit(1);
 				}
@@ -1558,7 +5099,67 @@ fn compile_ast<W: Write>(
 					struct_members.push((i.name, type_table.lookup_type(&i.t)?));
 				}
 
-				type_table.table.insert(name, Type::Struct(struct_members));
+				type_table
+					.table
+					.insert(name.clone(), Type::Struct(name, struct_members));
+			}
+			types::Root::Constant { name, t, value } => {
+				let t = type_table.lookup_type(&t)?;
+				let value = symbols.eval(&value)?;
+				symbols.define_constant(&name, t, value)?;
+				writeln!(output, "def {name} equ {value}")?;
+			}
+			types::Root::Extern {
+				name,
+				t,
+				is_constant,
+			} => {
+				let symbol = match type_table.lookup_type(&t)? {
+					Type::Primative(t) if is_constant => Symbol::ExternConstant(t),
+					t if is_constant => {
+						return Err(CompilerError::from(format!(
+							"Constants must be integers, not {t}"
+						)))
+					}
+					t => Symbol::Extern(t),
+				};
+				symbols.define_symbol(&name, symbol)?;
+			}
+			types::Root::Charmap { text, values } => {
+				symbols.define_charmap(text, &values)?;
+			}
+			types::Root::CharmapInclude(path) => {
+				include_charmap(&path, symbols)?;
+			}
+			types::Root::Enum { name, t, variants } => {
+				let t = match type_table.lookup_type(&t)? {
+					Type::Primative(t) => t,
+					t => {
+						return Err(CompilerError::from(format!(
+							"The values of {name} must be integers, not {t}"
+						)))
+					}
+				};
+
+				let enum_type = Type::Enum(name.clone(), t);
+
+				// Variants are referred to as `Enum.Variant`, and exported as `Enum_Variant`.
+				let mut next_value = 0;
+				for variant in variants {
+					let value = match variant.value {
+						Some(value) => symbols.eval(&value)?,
+						None => next_value,
+					};
+					symbols.define_constant(
+						&format!("{name}.{}", variant.name),
+						enum_type.clone(),
+						value,
+					)?;
+					writeln!(output, "def {name}_{} equ {value}", variant.name)?;
+					next_value = value + 1;
+				}
+
+				type_table.table.insert(name, enum_type);
 			}
 		}
 	}
@@ -1573,31 +5174,32 @@ pub fn compile<W: Write>(
 	options: CompilerOptions,
 ) -> Result<(), CompilerError> {
 	let mut environment_table = EnvironmentTable::new();
+	let mut symbol_table = SymbolTable::default();
 
 	let mut type_table = TypeTable {
-		table: HashMap::<String, Type>::from([
-			(
-				String::from("u8"),
-				Type::Primative(Primative {
-					signed: false,
-					size: 1,
-				}),
-			),
-			(
-				String::from("u16"),
-				Type::Primative(Primative {
-					signed: false,
-					size: 2,
-				}),
-			),
-		]),
+		table: HashMap::<String, Type>::new(),
 	};
+	// Built-in primatives from u8/i8 up to u32/i32.
+	for size in 1..=4 {
+		for signed in [false, true] {
+			let t = Primative {
+				signed,
+				size,
+				boolean: false,
+			};
+			type_table.table.insert(t.to_string(), Type::Primative(t));
+		}
+	}
+	type_table
+		.table
+		.insert(String::from("bool"), Type::Primative(Primative::boolean()));
 
 	writeln!(output, "def __EVSCRIPT_FILE__ equs {path:?}")?;
 	compile_ast(
 		ast,
 		&mut environment_table,
 		&mut type_table,
+		&mut symbol_table,
 		output,
 		&options,
 	)?;
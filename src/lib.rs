@@ -3,7 +3,7 @@ pub mod types;
 
 pub use compiler::compile;
 use lalrpop_util::lalrpop_mod;
-lalrpop_mod!(pub parser);
+lalrpop_mod!(#[allow(clippy::all)] pub parser);
 
 pub fn parse(
 	input: &str,
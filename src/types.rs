@@ -11,18 +11,47 @@ pub enum StatementType {
 	Use(String),
 	Definition(String, Definition),
 	Pool(Rpn),
+	DefaultInteger(String),
+	PointerType(String),
+	Constant(String, String, Rpn),
 	// Function statements
 	Expression(Rpn),
 	Declaration(String, String),
 	PointerDeclaration(String, String),
+	ArrayDeclaration(String, String, Rpn),
 	DeclareAssign(String, String, Rpn),
 	PointerDeclareAssign(String, String, Rpn),
 	If(Rpn, Vec<Statement>, Option<Vec<Statement>>),
-	While(Rpn, Vec<Statement>),
-	Do(Rpn, Vec<Statement>),
-	For(Box<Statement>, Rpn, Box<Statement>, Vec<Statement>),
-	Repeat(Rpn, Vec<Statement>),
-	Loop(Vec<Statement>),
+	// Loops begin with an optional label, which `break` and `continue` may refer to.
+	While(Option<String>, Rpn, Vec<Statement>),
+	Do(Option<String>, Rpn, Vec<Statement>),
+	For(
+		Option<String>,
+		Box<Statement>,
+		Rpn,
+		Box<Statement>,
+		Vec<Statement>,
+	),
+	Repeat(Option<String>, Option<String>, Rpn, Vec<Statement>),
+	Loop(Option<String>, Vec<Statement>),
+	Switch(Rpn, Vec<SwitchCase>, Option<Vec<Statement>>),
+	Break(Option<LoopTarget>),
+	Continue(Option<LoopTarget>),
+	Return(Rpn),
+}
+
+#[derive(Debug)]
+pub struct SwitchCase {
+	pub values: Vec<Rpn>,
+	pub contents: Vec<Statement>,
+}
+
+/// The loop a `break` or `continue` statement refers to.
+#[derive(Debug)]
+pub enum LoopTarget {
+	/// Counts outwards from the innermost loop, which is 1.
+	Depth(usize),
+	Label(String),
 }
 
 #[derive(Debug)]
@@ -39,6 +68,28 @@ pub enum Root {
 		name: String,
 		contents: Vec<StructMember>,
 	},
+	Constant {
+		name: String,
+		t: String,
+		value: Rpn,
+	},
+	Enum {
+		name: String,
+		t: String,
+		variants: Vec<EnumVariant>,
+	},
+	/// A symbol defined in assembly.
+	/// Normally this is the address of a variable of type `t`, but a constant extern is a value of type `t` instead.
+	Extern {
+		name: String,
+		t: String,
+		is_constant: bool,
+	},
+	Charmap {
+		text: String,
+		values: Vec<Rpn>,
+	},
+	CharmapInclude(String),
 }
 
 // Top-level statements.
@@ -50,17 +101,33 @@ pub struct Environment {
 #[derive(Debug)]
 pub struct Function {
 	pub environment: String,
+	pub args: Vec<FunctionParam>,
 	pub contents: Vec<Statement>,
 	pub start: usize,
 	pub end: usize,
 }
 
+/// A script parameter, placed at a fixed offset at the start of the pool.
+#[derive(Debug)]
+pub struct FunctionParam {
+	pub t: String,
+	pub is_pointer: bool,
+	pub name: String,
+}
+
 #[derive(Debug)]
 pub struct StructMember {
 	pub name: String,
 	pub t: String,
 }
 
+/// A variant without an explicit value is one greater than the previous variant.
+#[derive(Debug)]
+pub struct EnumVariant {
+	pub name: String,
+	pub value: Option<Rpn>,
+}
+
 // Environment statements
 #[derive(Debug, Clone)]
 pub enum Definition {
@@ -108,13 +175,22 @@ pub enum Rpn {
 	// Values
 	Variable(String),
 	Signed(i64),
+	Bool(bool),
 	String(String),
+	// A character literal, which may be a multi-character charmap entry such as `'<WAITBTN>'`.
+	Character(String),
 	Call(String, Vec<Rpn>),
+	// `call Script(args)`, which runs another script as a subroutine.
+	CallScript(String, Vec<Rpn>),
+	Index(String, Box<Rpn>),
+	Member(Box<Rpn>, String),
 	// Unary
 	Negate(Box<Rpn>),
 	Deref(Box<Rpn>),
 	Not(Box<Rpn>),
+	Complement(Box<Rpn>),
 	Address(String),
+	Cast(Box<Rpn>, String),
 	// Factors
 	Mul(Box<Rpn>, Box<Rpn>),
 	Div(Box<Rpn>, Box<Rpn>),
@@ -139,53 +215,179 @@ pub enum Rpn {
 	// Logicals
 	LogicalAnd(Box<Rpn>, Box<Rpn>),
 	LogicalOr(Box<Rpn>, Box<Rpn>),
-	// += is constructed using a Set(self, Add(self, <expression>))
+	// Conditionals
+	Ternary(Box<Rpn>, Box<Rpn>, Box<Rpn>),
+	// The current value of a compound assignment's target, such as the element in `a[i] += 1`.
+	// The target is only evaluated once, by the Set* node which this is the value of.
+	Current,
+	// += is constructed using a Set(self, Add(self, <expression>)), or Set*(target, Add(Current, <expression>)) for targets with side effects
 	Set(String, Box<Rpn>),
+	SetIndex(String, Box<Rpn>, Box<Rpn>),
+	SetDeref(Box<Rpn>, Box<Rpn>),
+	SetMember(Box<Rpn>, String, Box<Rpn>),
+}
+
+/// Provides the values of names and characters within constant expressions.
+pub trait ConstantTable {
+	/// Returns `None` if `name` is not a constant.
+	fn constant(&self, name: &str) -> Option<i64>;
+	fn character(&self, text: &str) -> Result<i64, String>;
 }
 
+/// The constructor of a binary Rpn node, such as `Rpn::Add`.
+pub type BinaryOperator = fn(Box<Rpn>, Box<Rpn>) -> Rpn;
+
 impl Rpn {
-	pub fn eval_const(&self) -> Result<i64, String> {
+	/// Whether this expression always results in a `bool`.
+	pub fn is_boolean(&self) -> bool {
+		matches!(
+			self,
+			Rpn::Bool(..)
+				| Rpn::Not(..)
+				| Rpn::Equ(..)
+				| Rpn::NotEqu(..)
+				| Rpn::LessThan(..)
+				| Rpn::GreaterThan(..)
+				| Rpn::LessThanEqu(..)
+				| Rpn::GreaterThanEqu(..)
+				| Rpn::LogicalAnd(..)
+				| Rpn::LogicalOr(..)
+		)
+	}
+
+	/// Whether this is the value of a compound assignment, which operates on `Rpn::Current`.
+	pub fn is_compound(&self) -> bool {
+		match self {
+			Rpn::Current => true,
+			Rpn::Mul(l, _)
+			| Rpn::Div(l, _)
+			| Rpn::Mod(l, _)
+			| Rpn::Add(l, _)
+			| Rpn::Sub(l, _)
+			| Rpn::ShiftLeft(l, _)
+			| Rpn::ShiftRight(l, _)
+			| Rpn::BinaryAnd(l, _)
+			| Rpn::BinaryXor(l, _)
+			| Rpn::BinaryOr(l, _) => l.is_compound(),
+			_ => false,
+		}
+	}
+
+	pub fn eval_const(&self, constants: &impl ConstantTable) -> Result<i64, String> {
 		Ok(match self {
-			Rpn::Variable(..) => {
-				return Err("Unexpected variable, expression must be constant".to_string())
-			}
+			Rpn::Variable(name) => match constants.constant(name) {
+				Some(value) => value,
+				None => return Err("Unexpected variable, expression must be constant".to_string()),
+			},
 			Rpn::String(..) => {
 				return Err("Unexpected string, expression must be constant".to_string())
 			}
-			Rpn::Call(..) => return Err("Unexpected call, expression must be constant".to_string()),
-			Rpn::Deref(..) => {
+			Rpn::Call(..) | Rpn::CallScript(..) => {
+				return Err("Unexpected call, expression must be constant".to_string())
+			}
+			Rpn::Index(..) => {
+				return Err("Unexpected array index, expression must be constant".to_string())
+			}
+			Rpn::Deref(..) | Rpn::Member(..) => {
 				return Err("Unexpected dereference, expression must be constant".to_string())
 			}
 			Rpn::Address(..) => {
 				return Err("Unexpected address operator, expression must be constant".to_string())
 			}
-			Rpn::Set(..) => {
+			Rpn::Cast(..) => return Err("Unexpected cast, expression must be constant".to_string()),
+			Rpn::Current
+			| Rpn::Set(..)
+			| Rpn::SetIndex(..)
+			| Rpn::SetDeref(..)
+			| Rpn::SetMember(..) => {
 				return Err("Unexpected assignment, expression must be constant".to_string())
 			}
 
 			Rpn::Signed(value) => *value,
+			Rpn::Bool(value) => *value as i64,
+			Rpn::Character(text) => constants.character(text)?,
+
+			Rpn::Negate(i) => -i.eval_const(constants)?,
+			Rpn::Not(i) => (i.eval_const(constants)? == 0) as i64,
+			Rpn::Complement(i) => !i.eval_const(constants)?,
 
-			Rpn::Negate(i) => i.eval_const()?,
-			Rpn::Not(i) => i.eval_const()?,
-
-			Rpn::Mul(l, r) => l.eval_const()? * r.eval_const()?,
-			Rpn::Div(l, r) => l.eval_const()? / r.eval_const()?,
-			Rpn::Mod(l, r) => l.eval_const()? % r.eval_const()?,
-			Rpn::Add(l, r) => l.eval_const()? + r.eval_const()?,
-			Rpn::Sub(l, r) => l.eval_const()? - r.eval_const()?,
-			Rpn::ShiftLeft(l, r) => l.eval_const()? << r.eval_const()?,
-			Rpn::ShiftRight(l, r) => l.eval_const()? >> r.eval_const()?,
-			Rpn::BinaryAnd(l, r) => l.eval_const()? & r.eval_const()?,
-			Rpn::BinaryXor(l, r) => l.eval_const()? ^ r.eval_const()?,
-			Rpn::BinaryOr(l, r) => l.eval_const()? | r.eval_const()?,
-			Rpn::Equ(l, r) => (l.eval_const()? == r.eval_const()?) as i64,
-			Rpn::NotEqu(l, r) => (l.eval_const()? != r.eval_const()?) as i64,
-			Rpn::LessThan(l, r) => (l.eval_const()? < r.eval_const()?) as i64,
-			Rpn::GreaterThan(l, r) => (l.eval_const()? > r.eval_const()?) as i64,
-			Rpn::LessThanEqu(l, r) => (l.eval_const()? <= r.eval_const()?) as i64,
-			Rpn::GreaterThanEqu(l, r) => (l.eval_const()? >= r.eval_const()?) as i64,
-			Rpn::LogicalAnd(l, r) => (l.eval_const()? != 0 && r.eval_const()? != 0) as i64,
-			Rpn::LogicalOr(l, r) => (l.eval_const()? != 0 || r.eval_const()? != 0) as i64,
+			Rpn::Mul(l, r) => l.eval_const(constants)? * r.eval_const(constants)?,
+			Rpn::Div(l, r) => l
+				.eval_const(constants)?
+				.checked_div(r.eval_const(constants)?)
+				.ok_or("Division by zero".to_string())?,
+			Rpn::Mod(l, r) => l
+				.eval_const(constants)?
+				.checked_rem(r.eval_const(constants)?)
+				.ok_or("Division by zero".to_string())?,
+			Rpn::Add(l, r) => l.eval_const(constants)? + r.eval_const(constants)?,
+			Rpn::Sub(l, r) => l.eval_const(constants)? - r.eval_const(constants)?,
+			Rpn::ShiftLeft(l, r) => l.eval_const(constants)? << r.eval_const(constants)?,
+			Rpn::ShiftRight(l, r) => l.eval_const(constants)? >> r.eval_const(constants)?,
+			Rpn::BinaryAnd(l, r) => l.eval_const(constants)? & r.eval_const(constants)?,
+			Rpn::BinaryXor(l, r) => l.eval_const(constants)? ^ r.eval_const(constants)?,
+			Rpn::BinaryOr(l, r) => l.eval_const(constants)? | r.eval_const(constants)?,
+			Rpn::Equ(l, r) => (l.eval_const(constants)? == r.eval_const(constants)?) as i64,
+			Rpn::NotEqu(l, r) => (l.eval_const(constants)? != r.eval_const(constants)?) as i64,
+			Rpn::LessThan(l, r) => (l.eval_const(constants)? < r.eval_const(constants)?) as i64,
+			Rpn::GreaterThan(l, r) => (l.eval_const(constants)? > r.eval_const(constants)?) as i64,
+			Rpn::LessThanEqu(l, r) => (l.eval_const(constants)? <= r.eval_const(constants)?) as i64,
+			Rpn::GreaterThanEqu(l, r) => {
+				(l.eval_const(constants)? >= r.eval_const(constants)?) as i64
+			}
+			Rpn::LogicalAnd(l, r) => {
+				(l.eval_const(constants)? != 0 && r.eval_const(constants)? != 0) as i64
+			}
+			Rpn::LogicalOr(l, r) => {
+				(l.eval_const(constants)? != 0 || r.eval_const(constants)? != 0) as i64
+			}
+			Rpn::Ternary(condition, if_true, if_false) => {
+				if condition.eval_const(constants)? != 0 {
+					if_true.eval_const(constants)?
+				} else {
+					if_false.eval_const(constants)?
+				}
+			}
 		})
 	}
 }
+
+/// Parses the digits of an integer literal, without its prefix.
+pub fn parse_integer(digits: &str, radix: u32) -> Result<Rpn, &'static str> {
+	i64::from_str_radix(digits, radix)
+		.map(Rpn::Signed)
+		.map_err(|_| "Integer is too large (maximum of 64 bits, signed)")
+}
+
+/// Processes the escape sequences within the contents of a string or character literal.
+pub fn unescape(text: &str) -> Result<String, &'static str> {
+	let mut result = String::new();
+	let mut chars = text.chars();
+
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			result.push(c);
+			continue;
+		}
+
+		result.push(match chars.next() {
+			Some('n') => '\n',
+			Some('r') => '\r',
+			Some('t') => '\t',
+			Some('0') => '\0',
+			Some('\\') => '\\',
+			Some('"') => '"',
+			Some('\'') => '\'',
+			Some('x') => {
+				let digits = chars.by_ref().take(2).collect::<String>();
+				match u8::from_str_radix(&digits, 16) {
+					Ok(byte) if digits.chars().all(|c| c.is_ascii_hexdigit()) => byte as char,
+					_ => return Err("Expected two hexadecimal digits after \\x"),
+				}
+			}
+			_ => return Err("Unknown escape sequence"),
+		});
+	}
+
+	Ok(result)
+}